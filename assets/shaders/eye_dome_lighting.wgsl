@@ -0,0 +1,64 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct EyeDomeLighting {
+    inverse_projection: mat4x4<f32>,
+    viewport: vec4<f32>,
+    strength: f32,
+    radius: f32,
+};
+
+@group(0) @binding(0)
+var screen_texture: texture_2d<f32>;
+#ifdef MULTISAMPLED
+@group(0) @binding(1)
+var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(1)
+var depth_texture: texture_depth_2d;
+#endif
+@group(0) @binding(2)
+var<uniform> settings: EyeDomeLighting;
+
+// the log2 depth differences at edges are small, e.g. 0.014 for a step of 1% in distance,
+// the scale brings them into a visible range at the default strength of 1
+const LOG_DEPTH_SCALE: f32 = 300.0;
+
+// log2 of the view space distance, zero for the background
+fn log_depth(position: vec2<i32>) -> f32 {
+    // sample index for multisampled and mip level otherwise
+    let depth = textureLoad(depth_texture, position, 0);
+    // reversed z, the far plane is at zero
+    if depth <= 0.0 {
+        return 0.0;
+    }
+    let view_position = settings.inverse_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    return log2(max(-view_position.z / view_position.w, 1e-6));
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let position = vec2<i32>(in.position.xy);
+    let color = textureLoad(screen_texture, position, 0);
+
+    // other views of the same render target are passed through
+    let viewport_min = vec2<i32>(settings.viewport.xy);
+    let viewport_max = viewport_min + vec2<i32>(settings.viewport.zw) - vec2<i32>(1);
+    if any(position < viewport_min) || any(position > viewport_max) {
+        return color;
+    }
+    let center = log_depth(position);
+    if center == 0.0 {
+        return color;
+    }
+
+    var response = 0.0;
+    for (var i = 0; i < 8; i += 1) {
+        let angle = f32(i) * 0.7853982;
+        let offset = vec2<i32>(round(vec2<f32>(cos(angle), sin(angle)) * settings.radius));
+        let neighbour = clamp(position + offset, viewport_min, viewport_max);
+        response += max(0.0, center - log_depth(neighbour));
+    }
+    response /= 8.0;
+    let shade = exp(-response * LOG_DEPTH_SCALE * settings.strength);
+    return vec4<f32>(color.rgb * shade, color.a);
+}
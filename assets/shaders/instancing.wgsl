@@ -4,16 +4,39 @@
 @group(1) @binding(0)
 var<uniform> mesh: Mesh;
 
+struct PointColors {
+    default_color: vec4<f32>,
+    color_mode: u32,
+    opacity: f32,
+    colors: array<vec4<f32>>,
+};
+
+struct PointSize {
+    size: f32,
+    min_pixels: f32,
+    max_pixels: f32,
+    attenuation: u32,
+};
+
+@group(2) @binding(0)
+var<storage> point_colors: PointColors;
+@group(2) @binding(1)
+var<uniform> point_size: PointSize;
+
 // NOTE: Bindings must come before functions that use them!
 #import bevy_pbr::mesh_functions
 
+const COLOR_MODE_LABEL: u32 = 0u;
+const COLOR_MODE_REMISSION: u32 = 1u;
+const NO_LABEL: u32 = 0xffffffffu;
+
 struct Vertex {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) uv: vec2<f32>,
 
-    @location(3) i_pos_scale: vec4<f32>,
-    @location(4) i_color: vec4<f32>,
+    @location(3) i_pos_remission: vec4<f32>,
+    @location(4) i_label: u32,
 };
 
 struct VertexOutput {
@@ -21,12 +44,45 @@ struct VertexOutput {
     @location(0) color: vec4<f32>,
 };
 
+fn label_color(label: u32) -> vec4<f32> {
+    if label == NO_LABEL || label >= arrayLength(&point_colors.colors) {
+        return point_colors.default_color;
+    }
+    return point_colors.colors[label];
+}
+
+fn remission_color(remission: f32) -> vec4<f32> {
+    // blue for weak, green for medium and red for strong returns
+    let value = clamp(remission, 0.0, 1.0);
+    let low = mix(vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(0.0, 1.0, 0.0), clamp(value * 2.0, 0.0, 1.0));
+    let color = mix(low, vec3<f32>(1.0, 0.0, 0.0), clamp(value * 2.0 - 1.0, 0.0, 1.0));
+    return vec4<f32>(color, point_colors.opacity);
+}
+
+// scale of the point to keep its on screen size in the pixel limits
+fn pixel_size_scale(center_clip_position: vec4<f32>) -> f32 {
+    if point_size.attenuation == 0u {
+        return 1.0;
+    }
+    let pixels = point_size.size * view.projection[1][1] * view.viewport.w * 0.5 / center_clip_position.w;
+    if pixels <= 0.0 {
+        return 1.0;
+    }
+    return clamp(pixels, point_size.min_pixels, point_size.max_pixels) / pixels;
+}
+
 @vertex
 fn vertex(vertex: Vertex) -> VertexOutput {
-    let position = vertex.position * vertex.i_pos_scale.w + vertex.i_pos_scale.xyz;
+    let center = vertex.i_pos_remission.xyz;
+    let center_clip_position = mesh_position_local_to_clip(mesh.model, vec4<f32>(center, 1.0));
+    let position = vertex.position * pixel_size_scale(center_clip_position) + center;
     var out: VertexOutput;
     out.clip_position = mesh_position_local_to_clip(mesh.model, vec4<f32>(position, 1.0));
-    out.color = vertex.i_color;
+    if point_colors.color_mode == COLOR_MODE_REMISSION {
+        out.color = remission_color(vertex.i_pos_remission.w);
+    } else {
+        out.color = label_color(vertex.i_label);
+    }
     return out;
 }
 
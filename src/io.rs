@@ -1,7 +1,9 @@
-use std::{fs::{File, self, ReadDir}, io::{Read, self}, path::PathBuf};
-use bevy::prelude::Vec3;
+use std::{cmp::Ordering, fs::{File, self, ReadDir}, io::{Read, self}, path::{Path, PathBuf}};
+use bevy::prelude::{Mat4, Ray, Vec3};
 use nom::{IResult, multi::many0, sequence::tuple, number::complete::{le_f32, le_u16}};
 
+use crate::plugins::Notification;
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Point{
     pub position: Vec3,
@@ -20,18 +22,71 @@ pub struct Frame{
     pub labels: Option<Vec<Label>>,
 }
 
+impl Frame{
+    /// Index of the nearest point hit by the ray, all points are assumed to be spheres with the given radius.
+    pub fn pick_point(&self, ray: Ray, radius: f32) -> Option<usize>{
+        let mut min_distance = f32::MAX;
+        let mut min_index = None;
+        let radius_squared = radius * radius;
+        for (iter, point) in self.points.iter().enumerate(){
+            let sphere_pos_to_ray_pos = point.position - ray.origin;
+            let projected_distance = sphere_pos_to_ray_pos.dot(ray.direction);
+            if projected_distance < 0.0 {
+                continue;
+            }
+            let distance_to_ray_squared = sphere_pos_to_ray_pos.length_squared() - projected_distance * projected_distance;
+            if distance_to_ray_squared > radius_squared {
+                continue;
+            }
+            if projected_distance < min_distance {
+                min_distance = projected_distance;
+                min_index = Some(iter);
+            }
+        }
+        min_index
+    }
+    /// Bytes occupied by the points and labels of the frame.
+    pub fn memory_size(&self) -> usize{
+        let labels = self.labels.as_ref().map_or(0, |labels| labels.len() * std::mem::size_of::<Label>());
+        self.points.len() * std::mem::size_of::<Point>() + labels
+    }
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum LoadState{
     NotRequested,
     Requested,
     Loaded,
+    /// Reading the files of the frame failed, it is not requested again.
+    Failed,
 }
 pub struct Sequence {
+    pub folder: PathBuf,
     pub point_folder: PathBuf, 
     pub label_folder: Option<PathBuf>,
     pub frames: Vec<Option<Frame>>,
     pub load_states: Vec<LoadState>,
     pub frame_count: usize,
+    /// File stem of each frame, the files are sorted by their frame number or timestamp.
+    pub frame_ids: Vec<String>,
+    /// Sensor pose of each frame in world coordinates, if the sequence has a `poses.txt`.
+    pub poses: Option<Vec<Mat4>>,
+    /// Why the existing `poses.txt` or `calib.txt` was ignored, the sequence is opened without poses.
+    pub pose_error: Option<String>,
+}
+
+impl Sequence{
+    pub fn point_path(&self, frame: usize) -> PathBuf{
+        self.point_folder.join(format!("{}.bin", self.frame_ids[frame]))
+    }
+    pub fn label_path(&self, frame: usize) -> Option<PathBuf>{
+        self.label_folder.as_ref().map(|folder| folder.join(format!("{}.label", self.frame_ids[frame])))
+    }
+    /// Tells that the sequence is opened without poses, if they could not be read.
+    pub fn pose_warning(&self) -> Option<Notification>{
+        let error = self.pose_error.as_ref()?;
+        Some(Notification::warning(format!("{error}\nThe sequence is shown without poses.")))
+    }
 }
 
 pub enum FrameReadError{
@@ -47,6 +102,16 @@ pub enum SequenceReadError{
         expected: usize,
         received: usize,
     },
+    ParsePoses(String),
+}
+
+impl std::fmt::Display for FrameReadError{
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self{
+            FrameReadError::ReadFile(error) => write!(formatter, "Cannot read file: {}", error),
+            FrameReadError::ParseFile(error) => write!(formatter, "Cannot parse file: {}", error),
+        }
+    }
 }
 
 impl std::fmt::Display for SequenceReadError{
@@ -57,82 +122,159 @@ impl std::fmt::Display for SequenceReadError{
             SequenceReadError::MissingFilesWithExtension(extension) => write!(formatter, "No '{}'-Files in Folder.", extension),
             SequenceReadError::LabelFilesCountMissmatch{expected, received} => 
                 write!(formatter, "The amount of label files mismatch the sequences frame amount.\n Label Files: {}\n Frames in Sequence:{}", received, expected),
+            SequenceReadError::ParsePoses(error) => write!(formatter, "Cannot read poses: {}", error),
          }
     }
 }
 
-fn count_files_with_extension(dir: ReadDir, extension: &str)-> usize{
-    let frame_files = dir.into_iter() .filter_map(|x| x.ok().map(|entry| entry.path())).filter(|path| match path.extension() {
-        Some(x) => x == extension,
-        None => false,
-    });
-    frame_files.count()
+pub fn frame_ids_with_extension(dir: ReadDir, extension: &str)-> Vec<String>{
+    let mut frame_ids: Vec<String> = dir.into_iter().filter_map(|x| x.ok().map(|entry| entry.path()))
+        .filter(|path| matches!(path.extension(), Some(x) if x == extension))
+        .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(|stem| stem.to_string()))
+        .collect();
+    frame_ids.sort_by(|a, b| compare_frame_ids(a, b));
+    frame_ids
+}
+/// Numeric ids like `000100` or `1618303231.52` are ordered by value and before all other ids, which are ordered by name.
+pub fn compare_frame_ids(a: &str, b: &str) -> Ordering{
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a_value), Ok(b_value)) => a_value.total_cmp(&b_value).then_with(|| a.cmp(b)),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
 }
-pub fn is_valid_label_dir(dir_path: PathBuf, frame_count: usize) -> Result<(), SequenceReadError>{
-    let read_dir = fs::read_dir(&dir_path).map_err(|e| SequenceReadError::ReadFolder(e))?;
-    match count_files_with_extension(read_dir, "label") {
-        label_count if label_count == frame_count => Ok(()), 
+/// Checks that every frame of the sequence has a label file with the same file stem.
+pub fn is_valid_label_dir(dir_path: PathBuf, frame_ids: &[String]) -> Result<(), SequenceReadError>{
+    if !dir_path.is_dir() {
+        return Err(SequenceReadError::FolderDontExist);
+    }
+    let label_count = frame_ids.iter().filter(|id| dir_path.join(format!("{}.label", id)).is_file()).count();
+    match label_count {
+        label_count if label_count == frame_ids.len() => Ok(()), 
         0 => Err(SequenceReadError::MissingFilesWithExtension("label".to_string())),
         label_count => Err(SequenceReadError::LabelFilesCountMissmatch { 
-            expected: label_count, 
-            received: frame_count }),
+            expected: frame_ids.len(), 
+            received: label_count }),
     }
 }
-pub fn read_sequence_from_dir(dir_path: PathBuf)-> Result<Sequence, SequenceReadError>{
+/// Folder with the '.bin' files: the `velodyne` folder of the sequence or, without one, the sequence folder itself.
+pub fn point_folder(dir_path: &Path) -> PathBuf{
     let velodyne_path = dir_path.join("velodyne");
-    let (frame_count, point_folder)  = match count_folder_files_with_extension(&velodyne_path, "bin") {
-        Ok(0) => return Err(SequenceReadError::MissingFilesWithExtension("bin".into())),    
-        Ok(count) => (count, velodyne_path),
-        Err(SequenceReadError::FolderDontExist) => match count_folder_files_with_extension(&dir_path, "bin"){
-            Ok(0) => return Err(SequenceReadError::MissingFilesWithExtension("bin".into())),    
-            Ok(count) => (count, dir_path.clone()),
-            Err(e) => return Err(e),
-        },
-        Err(e) => return Err(e),
-    };
+    match velodyne_path.is_dir() {
+        true => velodyne_path,
+        false => dir_path.to_path_buf(),
+    }
+}
+pub fn label_folder(dir_path: &Path) -> PathBuf{
+    dir_path.join("labels")
+}
+pub fn read_sequence_from_dir(dir_path: PathBuf)-> Result<Sequence, SequenceReadError>{
+    let point_folder = point_folder(&dir_path);
+    let frame_ids = folder_frame_ids(&point_folder, "bin")?;
+    if frame_ids.is_empty() {
+        return Err(SequenceReadError::MissingFilesWithExtension("bin".into()));
+    }
+    let frame_count = frame_ids.len();
 
-    let labels_path = dir_path.join("labels");
-    let label_folder = match count_folder_files_with_extension(&labels_path, "label") {
-        Ok(count) if frame_count == count => Some(labels_path),
-        Ok(_) => None, 
-        Err(SequenceReadError::FolderDontExist) => None,
-        Err(e) => return Err(e),
+    let labels_path = label_folder(&dir_path);
+    let label_folder = is_valid_label_dir(labels_path.clone(), &frame_ids).ok().map(|_| labels_path);
+
+    let (poses, pose_error) = match read_poses(&dir_path, frame_count) {
+        Ok(poses) => (poses, None),
+        Err(error) => (None, Some(error.to_string())),
     };
 
     Ok(Sequence{
+        folder: dir_path,
         point_folder,
         label_folder,
         frame_count,
+        frame_ids,
+        poses,
+        pose_error,
         load_states: vec![LoadState::NotRequested; frame_count],
         frames: std::iter::repeat_with(|| None).take(frame_count).collect(),
     })
 }
 
-fn count_folder_files_with_extension(folder: &PathBuf, extension: &str) -> Result<usize, SequenceReadError>{
+/// Reads the KITTI `poses.txt` and converts the camera poses into sensor poses with `Tr` of the `calib.txt`.
+/// The poses are returned in the axes of the viewer, where y and z of the sensor are swapped.
+fn read_poses(dir_path: &Path, frame_count: usize) -> Result<Option<Vec<Mat4>>, SequenceReadError>{
+    let poses_path = dir_path.join("poses.txt");
+    if !poses_path.is_file() {
+        return Ok(None);
+    }
+    let poses_text = fs::read_to_string(&poses_path).map_err(|e| SequenceReadError::ParsePoses(e.to_string()))?;
+    let camera_poses = poses_text.lines().filter(|line| !line.trim().is_empty()).enumerate()
+        .map(|(iter, line)| parse_matrix_3x4(line).ok_or_else(|| SequenceReadError::ParsePoses(format!("Invalid pose in line {}", iter + 1))))
+        .collect::<Result<Vec<Mat4>, SequenceReadError>>()?;
+    if camera_poses.len() != frame_count {
+        return Err(SequenceReadError::ParsePoses(format!("Expected {} poses, found {}", frame_count, camera_poses.len())));
+    }
+
+    let calib_path = dir_path.join("calib.txt");
+    let velodyne_to_camera = match calib_path.is_file() {
+        true => {
+            let calib_text = fs::read_to_string(&calib_path).map_err(|e| SequenceReadError::ParsePoses(e.to_string()))?;
+            calib_text.lines().find_map(|line| line.strip_prefix("Tr:")).and_then(parse_matrix_3x4)
+                .ok_or_else(|| SequenceReadError::ParsePoses("Missing 'Tr' in calib.txt".to_string()))?
+        },
+        false => Mat4::IDENTITY,
+    };
+    let camera_to_velodyne = velodyne_to_camera.inverse();
+    // swaps y and z, it is its own inverse
+    let swap_axes = Mat4::from_cols_array(&[
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]);
+    Ok(Some(camera_poses.into_iter().map(|camera_pose| swap_axes * camera_to_velodyne * camera_pose * velodyne_to_camera * swap_axes).collect()))
+}
+
+/// Parses 12 whitespace separated values of a row major 3x4 matrix.
+fn parse_matrix_3x4(text: &str) -> Option<Mat4>{
+    let values = text.split_whitespace().map(|value| value.parse::<f32>().ok()).collect::<Option<Vec<f32>>>()?;
+    let [r00, r01, r02, t0, r10, r11, r12, t1, r20, r21, r22, t2] = values[..] else {
+        return None;
+    };
+    Some(Mat4::from_cols_array(&[
+        r00, r10, r20, 0.0,
+        r01, r11, r21, 0.0,
+        r02, r12, r22, 0.0,
+        t0, t1, t2, 1.0,
+    ]))
+}
+
+fn folder_frame_ids(folder: &PathBuf, extension: &str) -> Result<Vec<String>, SequenceReadError>{
     if !folder.is_dir() {
         return Err(SequenceReadError::FolderDontExist);
     }
-    let read_dir = fs::read_dir(folder).map_err(|e| SequenceReadError::ReadFolder(e))?;
-    let frame_count = count_files_with_extension(read_dir, extension);
-    Ok(frame_count)
+    let read_dir = fs::read_dir(folder).map_err(SequenceReadError::ReadFolder)?;
+    Ok(frame_ids_with_extension(read_dir, extension))
 }
 
 pub fn read_frame(points_path: PathBuf, labels_path: Option<PathBuf>) -> Result<Frame, FrameReadError>{
-    let mut f = File::open(points_path).map_err(|e| FrameReadError::ReadFile(e))?;
+    let mut f = File::open(points_path).map_err(FrameReadError::ReadFile)?;
     let mut buffer = Vec::new();
-    f.read_to_end(&mut buffer).map_err(|e| FrameReadError::ReadFile(e))?;
+    f.read_to_end(&mut buffer).map_err(FrameReadError::ReadFile)?;
     let (_, points) = parse_points(&buffer).map_err(|e| FrameReadError::ParseFile(e.to_string()))?;
-    let mut labels = None;
-    if let Some(path) = labels_path { 
-        let mut f = File::open(path).map_err(|e| FrameReadError::ReadFile(e))?; 
-        let mut buffer = Vec::new();
-        f.read_to_end(&mut buffer).map_err(|e| FrameReadError::ReadFile(e))?;
-        let (_, label_data) = parse_labels(&buffer).map_err(|e| FrameReadError::ParseFile(e.to_string()))?;
-        labels = Some(label_data);
-    }
+    let labels = match labels_path {
+        Some(path) => Some(read_labels(path)?),
+        None => None,
+    };
     Ok(Frame{points, labels})
 }
 
+pub fn read_labels(labels_path: PathBuf) -> Result<Vec<Label>, FrameReadError>{
+    let mut f = File::open(labels_path).map_err(FrameReadError::ReadFile)?;
+    let mut buffer = Vec::new();
+    f.read_to_end(&mut buffer).map_err(FrameReadError::ReadFile)?;
+    let (_, labels) = parse_labels(&buffer).map_err(|e| FrameReadError::ParseFile(e.to_string()))?;
+    Ok(labels)
+}
+
 fn parse_points(input: &[u8]) -> IResult<&[u8], Vec<Point>>{
     many0(read_point)(input)
 }
@@ -150,3 +292,37 @@ fn read_label(input: &[u8]) -> IResult<&[u8], Label>{
     let (input, (label, instance_id)) = tuple((le_u16, le_u16))(input)?;
     Ok((input, Label{label, instance_id}))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_matrix_3x4_reads_rows() {
+        let matrix = parse_matrix_3x4("1 2 3 4 5 6 7 8 9 10 11 12").unwrap();
+        assert_eq!(matrix.row(0), bevy::math::Vec4::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(matrix.row(1), bevy::math::Vec4::new(5.0, 6.0, 7.0, 8.0));
+        assert_eq!(matrix.row(2), bevy::math::Vec4::new(9.0, 10.0, 11.0, 12.0));
+        assert_eq!(matrix.row(3), bevy::math::Vec4::new(0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn parse_matrix_3x4_rejects_invalid_rows() {
+        assert!(parse_matrix_3x4("1 2 3 4 5 6 7 8 9 10 11").is_none());
+        assert!(parse_matrix_3x4("1 2 3 4 5 6 7 8 9 10 11 12 13").is_none());
+        assert!(parse_matrix_3x4("1 2 3 4 5 6 7 8 9 10 11 x").is_none());
+    }
+
+    #[test]
+    fn compare_frame_ids_orders_numbers_by_value_before_names() {
+        let mut frame_ids = vec!["b", "10", "000100", "a", "9", "1618303231.52", "1618303231.6"];
+        frame_ids.sort_by(|a, b| compare_frame_ids(a, b));
+        assert_eq!(frame_ids, vec!["9", "10", "000100", "1618303231.52", "1618303231.6", "a", "b"]);
+    }
+
+    #[test]
+    fn compare_frame_ids_keeps_equal_values_apart() {
+        assert_eq!(compare_frame_ids("0100", "100"), Ordering::Less);
+        assert_eq!(compare_frame_ids("100", "100"), Ordering::Equal);
+    }
+}
@@ -2,8 +2,9 @@
 pub mod io;
 pub mod math;
 pub mod plugins;
+pub mod validation;
 
-use std::io::Cursor;
+use std::{io::Cursor, path::Path};
 
 use bevy::{prelude::*, winit::WinitWindows, window::PrimaryWindow};
 use bevy_embedded_assets::EmbeddedAssetPlugin;
@@ -11,6 +12,18 @@ use plugins::*;
 use winit::window::Icon;
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--validate") {
+        #[cfg(windows)]
+        attach_parent_console();
+        let Some(folder) = args.get(index + 1) else {
+            eprintln!("Usage: {} --validate <sequence folder>", args[0]);
+            std::process::exit(2);
+        };
+        let report = validation::validate_sequence(Path::new(folder));
+        print!("{report}");
+        std::process::exit(if report.is_valid() { 0 } else { 1 });
+    }
     App::new()
         .add_startup_systems((setup_window, set_window_icon))
         .add_plugins(
@@ -19,13 +32,32 @@ fn main() {
                 .add_before::<bevy::asset::AssetPlugin, _>(EmbeddedAssetPlugin),
         )
         .add_plugin(ConfigPlugin)
+        .add_plugin(NotificationPlugin)
         .add_plugin(LidarPlugin)
         .add_plugin(FpsWindowTitlePlugin)
         .add_plugin(ObserverPlugin)
+        .add_plugin(ReferencePlugin)
+        .add_plugin(CameraPathPlugin)
+        .add_plugin(AnnotationsPlugin)
+        .add_plugin(HeatmapPlugin)
         .add_plugin(UiPlugin)
         .run();
 }
 
+/// Release builds use the windows subsystem and start without a console, the report would not be printed.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // fails if there is no parent console or the process already has one, printing is then just lost
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 fn setup_window(mut query_window: Query<&mut Window>) {
     let mut window = query_window.single_mut();
     window.title = "Lidar Sequence Viewer".to_string();
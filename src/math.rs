@@ -1,3 +1,5 @@
+use bevy::prelude::Vec3;
+
 // from Game Programming Gems 4 chapter 1.10 and unitys smooth damp
 pub fn smooth_damp(
     current: f32,
@@ -28,3 +30,13 @@ pub fn lerp(alpha: f32, min: f32, max: f32) -> f32 {
 pub fn inv_lerp(value: f32, min: f32, max: f32) -> f32 {
     (value - min) / (max - min)
 }
+
+/// Uniform Catmull-Rom spline between `p1` and `p2`.
+pub fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
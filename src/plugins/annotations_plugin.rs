@@ -0,0 +1,156 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{lidar::PlayerState, Notification};
+
+/// Named marks at sequence frames, stored next to the sequence to share them with others.
+pub struct AnnotationsPlugin;
+
+impl Plugin for AnnotationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TimelineAnnotations::default())
+            .add_system(load_annotations);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    /// File stem of the frame, the index would point to another frame once files are added or removed.
+    pub frame_id: String,
+    pub text: String,
+    /// Index of the frame in the opened sequence, none if the sequence has no frame with the id.
+    #[serde(skip)]
+    pub frame: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AnnotationFile {
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Resource, Default)]
+pub struct TimelineAnnotations {
+    /// Sorted by frame, a frame can hold several annotations.
+    pub annotations: Vec<Annotation>,
+    folder: Option<PathBuf>,
+    /// Changed since the last save or reload.
+    modified: bool,
+}
+
+impl TimelineAnnotations {
+    const FILE_NAME: &str = "annotations.ron";
+
+    pub fn file_path(&self) -> Option<PathBuf> {
+        Some(self.folder.as_ref()?.join(Self::FILE_NAME))
+    }
+    pub fn insert(&mut self, frame: usize, frame_id: String, text: String) {
+        let index = self.annotations.partition_point(
+            |annotation| matches!(annotation.frame, Some(other) if other <= frame),
+        );
+        let annotation = Annotation {
+            frame_id,
+            text,
+            frame: Some(frame),
+        };
+        self.annotations.insert(index, annotation);
+        self.modified = true;
+    }
+    pub fn remove(&mut self, index: usize) {
+        self.annotations.remove(index);
+        self.modified = true;
+    }
+    /// To be called after editing the text of an annotation in place.
+    pub fn mark_modified(&mut self) {
+        self.modified = true;
+    }
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+    /// Annotation closest to the frame, if one is at most `tolerance` frames away.
+    pub fn near(&self, frame: f64, tolerance: f64) -> Option<&Annotation> {
+        self.annotations
+            .iter()
+            .filter_map(|annotation| Some((annotation, annotation.frame? as f64)))
+            .map(|(annotation, annotation_frame)| (annotation, (annotation_frame - frame).abs()))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(annotation, _)| annotation)
+    }
+    /// Warning about annotations of frames missing in the sequence, e.g. of a file written for a longer sequence.
+    pub fn missing_frames_warning(&self) -> Option<Notification> {
+        let count = self
+            .annotations
+            .iter()
+            .filter(|annotation| annotation.frame.is_none())
+            .count();
+        (count > 0).then(|| {
+            Notification::warning(format!("{count} annotations are at frames missing in the sequence and are not shown on the timeline."))
+        })
+    }
+    pub fn save(&mut self) -> Result<(), String> {
+        let file_path = self.file_path().ok_or("No sequence is opened.")?;
+        let file = AnnotationFile {
+            annotations: self.annotations.clone(),
+        };
+        let text = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        fs::write(&file_path, text)
+            .map_err(|error| format!("Cannot write {file_path:?}\n{error}"))?;
+        self.modified = false;
+        Ok(())
+    }
+    /// Replaces the annotations with the file of the sequence, no file results in no annotations.
+    pub fn reload(&mut self, player: &PlayerState) -> Result<(), String> {
+        self.annotations.clear();
+        self.modified = false;
+        let Some(file_path) = self.file_path() else {
+            return Ok(());
+        };
+        if !file_path.is_file() {
+            return Ok(());
+        }
+        let text = fs::read_to_string(&file_path)
+            .map_err(|error| format!("Cannot read {file_path:?}\n{error}"))?;
+        let mut file: AnnotationFile =
+            ron::from_str(&text).map_err(|error| format!("Cannot parse {file_path:?}\n{error}"))?;
+        for annotation in &mut file.annotations {
+            annotation.frame = player.get_frame_index(&annotation.frame_id);
+        }
+        // annotations of missing frames last
+        file.annotations
+            .sort_by_key(|annotation| annotation.frame.unwrap_or(usize::MAX));
+        self.annotations = file.annotations;
+        Ok(())
+    }
+}
+
+fn load_annotations(
+    player: Res<PlayerState>,
+    mut annotations: ResMut<TimelineAnnotations>,
+    mut notifications: EventWriter<Notification>,
+) {
+    let folder = player.get_sequence_folder();
+    if folder == annotations.folder.as_deref() {
+        return;
+    }
+    // keep the notes of the previous sequence instead of dropping them unnoticed
+    if annotations.is_modified() {
+        match annotations.save() {
+            Ok(()) => {
+                if let Some(file_path) = annotations.file_path() {
+                    notifications.send(Notification::info(format!(
+                        "Saved the changed annotations to {file_path:?}"
+                    )));
+                }
+            }
+            Err(error) => notifications.send(Notification::error(error)),
+        }
+    }
+    annotations.folder = folder.map(|folder| folder.to_path_buf());
+    match annotations.reload(&player) {
+        Ok(()) => notifications.send_batch(annotations.missing_frames_warning()),
+        Err(error) => notifications.send(Notification::error(error)),
+    }
+}
@@ -0,0 +1,178 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    lidar::PlayerState,
+    observer_plugin::{CameraController, Viewport},
+    Notification, PlayerConfig,
+};
+use crate::math::catmull_rom;
+
+/// Moves the camera along keyframes placed at sequence frames while the sequence plays.
+pub struct CameraPathPlugin;
+
+impl Plugin for CameraPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CameraPath::default())
+            .add_system(load_camera_path)
+            .add_system(animate_camera_path.after(load_camera_path));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    pub frame: usize,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+}
+
+impl CameraKeyframe {
+    fn translation(&self) -> Vec3 {
+        Vec3::from_array(self.translation)
+    }
+    fn rotation(&self) -> Quat {
+        Quat::from_array(self.rotation).normalize()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CameraPathFile {
+    keyframes: Vec<CameraKeyframe>,
+}
+
+#[derive(Resource, Default)]
+pub struct CameraPath {
+    /// Sorted by frame, at most one keyframe per frame.
+    pub keyframes: Vec<CameraKeyframe>,
+    pub active: bool,
+    folder: Option<PathBuf>,
+}
+
+impl CameraPath {
+    const FILE_NAME: &str = "camera_path.ron";
+
+    pub fn file_path(&self) -> Option<PathBuf> {
+        Some(self.folder.as_ref()?.join(Self::FILE_NAME))
+    }
+    pub fn insert_keyframe(&mut self, frame: usize, transform: &Transform) {
+        let keyframe = CameraKeyframe {
+            frame,
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+        };
+        match self
+            .keyframes
+            .binary_search_by_key(&frame, |keyframe| keyframe.frame)
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+    pub fn save(&self) -> Result<(), String> {
+        let file_path = self.file_path().ok_or("No sequence is opened.")?;
+        let file = CameraPathFile {
+            keyframes: self.keyframes.clone(),
+        };
+        let text = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        fs::write(&file_path, text).map_err(|error| format!("Cannot write {file_path:?}\n{error}"))
+    }
+    /// Replaces the keyframes with the file of the sequence, no file results in an empty path.
+    pub fn reload(&mut self) -> Result<(), String> {
+        self.keyframes.clear();
+        self.active = false;
+        let Some(file_path) = self.file_path() else {
+            return Ok(());
+        };
+        if !file_path.is_file() {
+            return Ok(());
+        }
+        let text = fs::read_to_string(&file_path)
+            .map_err(|error| format!("Cannot read {file_path:?}\n{error}"))?;
+        let mut file: CameraPathFile =
+            ron::from_str(&text).map_err(|error| format!("Cannot parse {file_path:?}\n{error}"))?;
+        file.keyframes.sort_by_key(|keyframe| keyframe.frame);
+        file.keyframes.dedup_by_key(|keyframe| keyframe.frame);
+        self.keyframes = file.keyframes;
+        Ok(())
+    }
+    /// Camera pose at a frame, the position follows a spline through the keyframes.
+    fn sample(&self, frame: f64) -> Option<Transform> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if frame <= first.frame as f64 {
+            return Some(
+                Transform::from_translation(first.translation()).with_rotation(first.rotation()),
+            );
+        }
+        if frame >= last.frame as f64 {
+            return Some(
+                Transform::from_translation(last.translation()).with_rotation(last.rotation()),
+            );
+        }
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.frame as f64 <= frame);
+        let previous = next - 1;
+        let start = &self.keyframes[previous];
+        let end = &self.keyframes[next];
+        let before_start = &self.keyframes[previous.saturating_sub(1)];
+        let after_end = &self.keyframes[(next + 1).min(self.keyframes.len() - 1)];
+        let alpha = ((frame - start.frame as f64) / (end.frame - start.frame) as f64) as f32;
+        let translation = catmull_rom(
+            before_start.translation(),
+            start.translation(),
+            end.translation(),
+            after_end.translation(),
+            alpha,
+        );
+        let rotation = start.rotation().slerp(end.rotation(), alpha);
+        Some(Transform::from_translation(translation).with_rotation(rotation))
+    }
+}
+
+fn load_camera_path(
+    player: Res<PlayerState>,
+    mut camera_path: ResMut<CameraPath>,
+    mut notifications: EventWriter<Notification>,
+) {
+    let folder = player.get_sequence_folder();
+    if folder == camera_path.folder.as_deref() {
+        return;
+    }
+    camera_path.folder = folder.map(|folder| folder.to_path_buf());
+    if let Err(error) = camera_path.reload() {
+        notifications.send(Notification::error(error));
+    }
+}
+
+fn animate_camera_path(
+    time: Res<Time>,
+    player: Res<PlayerState>,
+    camera_path: Res<CameraPath>,
+    config: Res<PlayerConfig>,
+    mut query: Query<(&mut Transform, &mut CameraController, &Viewport)>,
+    mut was_active: Local<bool>,
+) {
+    let active = camera_path.active && !camera_path.keyframes.is_empty();
+    // the path drives the main viewport only
+    for (mut transform, mut controller, viewport) in query.iter_mut() {
+        if !viewport.is_main() {
+            continue;
+        }
+        if !active {
+            if *was_active {
+                controller.enabled = true;
+                controller.sync_to_transform(&transform, config.persistent.camera_mode);
+            }
+            continue;
+        }
+        controller.enabled = false;
+        if let Some(pose) = camera_path.sample(player.get_exact_frame(time.elapsed_seconds_f64())) {
+            *transform = pose;
+        }
+    }
+    *was_active = active;
+}
@@ -1,8 +1,10 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, ops::RangeInclusive};
 
 use bevy::{prelude::*, utils::HashMap};
 use serde::{Deserialize, Serialize};
 
+use super::{Action, KeyBindings, Notification};
+
 type ColorRgbU8 = [u8; 3];
 type ColorRgbaF32 = [f32; 4];
 
@@ -10,6 +12,11 @@ type ColorRgbaF32 = [f32; 4];
 pub struct LabelInfo {
     pub name: String,
     pub color: ColorRgbU8,
+    #[serde(default)]
+    pub hidden: bool,
+    /// Counted in the heatmap on the timeline.
+    #[serde(default)]
+    pub heatmap: bool,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -21,19 +28,340 @@ pub struct Config {
     pub camera_speed: f32,
     pub point_size: f32,
     pub sensor_fps: f64,
+    #[serde(default)]
+    pub playback_mode: PlaybackMode,
+    #[serde(default)]
+    pub frame_error_policy: FrameErrorPolicy,
+    /// Memory the loaded frames may occupy, the least recently used frames are freed first.
+    #[serde(default = "default_memory_budget_mb")]
+    pub memory_budget_mb: u32,
+    #[serde(default)]
+    pub color_mode: ColorMode,
+    #[serde(default = "default_opacity")]
+    pub point_opacity: f32,
+    #[serde(default = "default_hidden_label_opacity")]
+    pub hidden_label_opacity: f32,
+    #[serde(default)]
+    pub edl_enabled: bool,
+    #[serde(default = "default_edl_strength")]
+    pub edl_strength: f32,
+    #[serde(default = "default_edl_radius")]
+    pub edl_radius: f32,
+    /// Off keeps the points at their size in meters, as before the pixel limits existed.
+    #[serde(default)]
+    pub size_attenuation: bool,
+    #[serde(default = "default_min_point_pixels")]
+    pub min_point_pixels: f32,
+    #[serde(default = "default_max_point_pixels")]
+    pub max_point_pixels: f32,
+    #[serde(default)]
+    pub lod_enabled: bool,
+    #[serde(default = "default_lod_distance")]
+    pub lod_distance: f32,
+    #[serde(default = "default_lod_voxel_size")]
+    pub lod_voxel_size: f32,
+    #[serde(default)]
+    pub grid_visible: bool,
+    #[serde(default = "default_grid_spacing")]
+    pub grid_spacing: f32,
+    #[serde(default = "default_grid_extent")]
+    pub grid_extent: f32,
+    #[serde(default = "default_grid_height")]
+    pub grid_height: f32,
+    #[serde(default)]
+    pub axes_visible: bool,
+    #[serde(default)]
+    pub range_rings_visible: bool,
+    #[serde(default = "default_range_ring_spacing")]
+    pub range_ring_spacing: f32,
+    #[serde(default = "default_range_ring_count")]
+    pub range_ring_count: u32,
+    /// Camera mode of the main viewport.
+    #[serde(default)]
+    pub camera_mode: CameraMode,
+    #[serde(default)]
+    pub viewport_layout: ViewportLayout,
+    /// Camera modes of the additional viewports.
+    #[serde(default = "default_viewport_camera_modes")]
+    pub viewport_camera_modes: [CameraMode; 3],
+    #[serde(default)]
+    pub world_coordinates: bool,
+    #[serde(default)]
+    pub camera_follow: CameraFollow,
+    #[serde(default)]
+    pub camera_bookmarks: Vec<CameraBookmark>,
+    /// Bookmarks saved and recalled with the number keys, index 0 belongs to key 1.
+    #[serde(default)]
+    pub numbered_bookmarks: [Option<CameraBookmark>; Action::BOOKMARK_SLOTS],
+    #[serde(default)]
+    pub key_bindings: KeyBindings,
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_hidden_label_opacity() -> f32 {
+    0.1
+}
+
+fn default_edl_strength() -> f32 {
+    1.0
+}
+
+fn default_edl_radius() -> f32 {
+    1.4
+}
+
+fn default_min_point_pixels() -> f32 {
+    1.0
+}
+
+fn default_max_point_pixels() -> f32 {
+    32.0
+}
+
+fn default_lod_distance() -> f32 {
+    30.0
+}
+
+fn default_lod_voxel_size() -> f32 {
+    0.5
+}
+
+fn default_grid_spacing() -> f32 {
+    1.0
+}
+
+fn default_grid_extent() -> f32 {
+    50.0
+}
+
+// mounting height of the velodyne in the KITTI setup
+fn default_grid_height() -> f32 {
+    -1.73
+}
+
+fn default_range_ring_spacing() -> f32 {
+    10.0
+}
+
+fn default_range_ring_count() -> u32 {
+    5
+}
+
+fn default_memory_budget_mb() -> u32 {
+    Config::DEFAULT_MEMORY_BUDGET_MB
+}
+
+fn default_viewport_camera_modes() -> [CameraMode; 3] {
+    [CameraMode::TopDown, CameraMode::Side, CameraMode::Front]
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    Label,
+    Remission,
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 2] = [ColorMode::Label, ColorMode::Remission];
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMode::Label => "Label",
+            ColorMode::Remission => "Remission",
+        }
+    }
+}
+
+/// What the player does at the end of the sequence or of the A–B range.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaybackMode {
+    #[default]
+    Once,
+    Loop,
+    PingPong,
+}
+
+impl PlaybackMode {
+    pub const ALL: [PlaybackMode; 3] = [
+        PlaybackMode::Once,
+        PlaybackMode::Loop,
+        PlaybackMode::PingPong,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlaybackMode::Once => "Once",
+            PlaybackMode::Loop => "Loop",
+            PlaybackMode::PingPong => "Ping-Pong",
+        }
+    }
+    pub fn next(&self) -> Self {
+        match self {
+            PlaybackMode::Once => PlaybackMode::Loop,
+            PlaybackMode::Loop => PlaybackMode::PingPong,
+            PlaybackMode::PingPong => PlaybackMode::Once,
+        }
+    }
 }
+
+/// What the player does when it reaches a frame that could not be read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameErrorPolicy {
+    #[default]
+    Skip,
+    Stop,
+}
+
+impl FrameErrorPolicy {
+    pub const ALL: [FrameErrorPolicy; 2] = [FrameErrorPolicy::Skip, FrameErrorPolicy::Stop];
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameErrorPolicy::Skip => "Skip",
+            FrameErrorPolicy::Stop => "Stop",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CameraMode {
+    #[default]
+    Fly,
+    Orbit,
+    TopDown,
+    Side,
+    Front,
+}
+
+impl CameraMode {
+    pub const ALL: [CameraMode; 5] = [
+        CameraMode::Fly,
+        CameraMode::Orbit,
+        CameraMode::TopDown,
+        CameraMode::Side,
+        CameraMode::Front,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Fly => "Fly",
+            CameraMode::Orbit => "Orbit",
+            CameraMode::TopDown => "Bird's-Eye View",
+            CameraMode::Side => "Side View",
+            CameraMode::Front => "Front View",
+        }
+    }
+    pub fn next(&self) -> Self {
+        match self {
+            CameraMode::Fly => CameraMode::Orbit,
+            CameraMode::Orbit => CameraMode::TopDown,
+            CameraMode::TopDown => CameraMode::Side,
+            CameraMode::Side => CameraMode::Front,
+            CameraMode::Front => CameraMode::Fly,
+        }
+    }
+    pub fn is_orthographic(&self) -> bool {
+        matches!(
+            self,
+            CameraMode::TopDown | CameraMode::Side | CameraMode::Front
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ViewportLayout {
+    #[default]
+    Single,
+    Double,
+    Quad,
+}
+
+impl ViewportLayout {
+    pub const ALL: [ViewportLayout; 3] = [
+        ViewportLayout::Single,
+        ViewportLayout::Double,
+        ViewportLayout::Quad,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            ViewportLayout::Single => "Single",
+            ViewportLayout::Double => "Side by Side",
+            ViewportLayout::Quad => "Four Viewports",
+        }
+    }
+    pub fn count(&self) -> usize {
+        match self {
+            ViewportLayout::Single => 1,
+            ViewportLayout::Double => 2,
+            ViewportLayout::Quad => 4,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CameraFollow {
+    #[default]
+    Off,
+    Chase,
+    Sensor,
+}
+
+impl CameraFollow {
+    pub const ALL: [CameraFollow; 3] =
+        [CameraFollow::Off, CameraFollow::Chase, CameraFollow::Sensor];
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraFollow::Off => "Off",
+            CameraFollow::Chase => "Chase",
+            CameraFollow::Sensor => "Fixed to Sensor",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub camera_mode: CameraMode,
+    pub fov_degrees: f32,
+    pub top_down_scale: f32,
+    pub pivot: [f32; 3],
+}
+
+impl Config {
+    pub const DEFAULT_MEMORY_BUDGET_MB: u32 = 2048;
+    /// A budget below a single frame would stall loading.
+    pub const MEMORY_BUDGET_RANGE_MB: RangeInclusive<u32> = 64..=65536;
+    /// The main viewport has the index 0.
+    pub fn camera_mode_of(&self, viewport: usize) -> CameraMode {
+        match viewport {
+            0 => self.camera_mode,
+            _ => self.viewport_camera_modes[viewport - 1],
+        }
+    }
+    pub fn set_camera_mode(&mut self, viewport: usize, mode: CameraMode) {
+        match viewport {
+            0 => self.camera_mode = mode,
+            _ => self.viewport_camera_modes[viewport - 1] = mode,
+        }
+    }
+}
+
 impl From<(&str, [u8; 3])> for LabelInfo {
     fn from(value: (&str, [u8; 3])) -> Self {
         Self {
             name: value.0.to_string(),
             color: value.1,
+            hidden: false,
+            heatmap: false,
         }
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self {
+        let mut config = Self {
             label_map: BTreeMap::from([
                 (0, ("unlabeled", [0, 0, 0]).into()),
                 (1, ("outlier", [0, 0, 255]).into()),
@@ -77,7 +405,45 @@ impl Default for Config {
             camera_speed: 10.0,
             point_size: 0.04,
             sensor_fps: 10.0,
+            playback_mode: PlaybackMode::Once,
+            frame_error_policy: FrameErrorPolicy::Skip,
+            memory_budget_mb: default_memory_budget_mb(),
+            color_mode: ColorMode::Label,
+            point_opacity: default_opacity(),
+            hidden_label_opacity: default_hidden_label_opacity(),
+            edl_enabled: false,
+            edl_strength: default_edl_strength(),
+            edl_radius: default_edl_radius(),
+            size_attenuation: false,
+            min_point_pixels: default_min_point_pixels(),
+            max_point_pixels: default_max_point_pixels(),
+            lod_enabled: false,
+            lod_distance: default_lod_distance(),
+            lod_voxel_size: default_lod_voxel_size(),
+            grid_visible: false,
+            grid_spacing: default_grid_spacing(),
+            grid_extent: default_grid_extent(),
+            grid_height: default_grid_height(),
+            axes_visible: false,
+            range_rings_visible: false,
+            range_ring_spacing: default_range_ring_spacing(),
+            range_ring_count: default_range_ring_count(),
+            camera_mode: CameraMode::Fly,
+            viewport_layout: ViewportLayout::Single,
+            viewport_camera_modes: default_viewport_camera_modes(),
+            world_coordinates: false,
+            camera_follow: CameraFollow::Off,
+            camera_bookmarks: Vec::new(),
+            numbered_bookmarks: Default::default(),
+            key_bindings: KeyBindings::default(),
+        };
+        // rare classes worth finding in a sequence
+        for label in [30, 31, 32] {
+            if let Some(info) = config.label_map.get_mut(&label) {
+                info.heatmap = true;
+            }
         }
+        config
     }
 }
 
@@ -86,6 +452,8 @@ pub struct PlayerConfig {
     pub persistent: Config,
     pub actual_color_map: HashMap<u16, ColorRgbaF32>,
     pub default_color: ColorRgbaF32,
+    /// Failed reads and writes of the config file, reported as notifications.
+    errors: Vec<String>,
 }
 
 impl PlayerConfig {
@@ -94,29 +462,32 @@ impl PlayerConfig {
         match confy::load(Self::APP_NAME, None) {
             Ok(config) => self.persistent = config,
             Err(error) => {
-                let file_path = confy::get_configuration_file_path(Self::APP_NAME, None).unwrap_or_default();
-                rfd::MessageDialog::new()
-                    .set_title("Error")
-                    .set_description(&format!("Cannot read config file: {file_path:?}\n{error}"))
-                    .set_buttons(rfd::MessageButtons::Ok)
-                    .set_level(rfd::MessageLevel::Error)
-                    .show();
+                let file_path =
+                    confy::get_configuration_file_path(Self::APP_NAME, None).unwrap_or_default();
+                self.push_error(format!("Cannot read config file: {file_path:?}\n{error}"));
                 return;
             }
         };
+        let budget = &mut self.persistent.memory_budget_mb;
+        *budget = (*budget).clamp(
+            *Config::MEMORY_BUDGET_RANGE_MB.start(),
+            *Config::MEMORY_BUDGET_RANGE_MB.end(),
+        );
         self.update_label_map();
     }
-    pub fn save(&self) {
+    pub fn save(&mut self) {
         if let Err(error) = confy::store(Self::APP_NAME, None, &self.persistent) {
-            let file_path = confy::get_configuration_file_path(Self::APP_NAME, None).unwrap_or_default();
-            rfd::MessageDialog::new()
-                .set_title("Error")
-                .set_description(&format!("Cannot save config file: {file_path:?}\n{error}"))
-                .set_buttons(rfd::MessageButtons::Ok)
-                .set_level(rfd::MessageLevel::Error)
-                .show();
+            let file_path =
+                confy::get_configuration_file_path(Self::APP_NAME, None).unwrap_or_default();
+            self.push_error(format!("Cannot save config file: {file_path:?}\n{error}"));
         };
     }
+    /// Drag values of the settings save on every change, a failing write would repeat the same error each frame.
+    fn push_error(&mut self, error: String) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
     pub fn reset_label_map(&mut self) {
         self.persistent.label_map = Self::default().persistent.label_map;
         self.update_label_map();
@@ -142,9 +513,33 @@ impl Plugin for ConfigPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.insert_resource(PlayerConfig::default());
         app.add_startup_system(load_config);
+        app.add_system(report_config_errors);
     }
 }
 
 fn load_config(mut config: ResMut<PlayerConfig>) {
     config.load();
 }
+
+/// An error is reported again only after `REPEAT_INTERVAL` seconds, while it keeps occurring.
+fn report_config_errors(
+    mut config: ResMut<PlayerConfig>,
+    mut notifications: EventWriter<Notification>,
+    time: Res<Time>,
+    mut last_reported: Local<HashMap<String, f64>>,
+) {
+    const REPEAT_INTERVAL: f64 = 10.0;
+    // only borrowed mutably with errors, the config is not marked as changed every frame
+    if config.errors.is_empty() {
+        return;
+    }
+    let now = time.elapsed_seconds_f64();
+    last_reported.retain(|_, reported| now - *reported < REPEAT_INTERVAL);
+    for error in config.errors.drain(..) {
+        if last_reported.contains_key(&error) {
+            continue;
+        }
+        last_reported.insert(error.clone(), now);
+        notifications.send(Notification::error(error));
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+
+use super::{lidar::PlayerState, PlayerConfig};
+use crate::io::read_labels;
+
+/// Counts the points of the selected classes in every label file of the sequence.
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LabelHeatmap::default())
+            .add_system(update_label_heatmap);
+    }
+}
+
+/// Label folder, frame ids and sorted classes the counts belong to, the ids name the label files.
+type HeatmapSource = (PathBuf, Vec<String>, Vec<u16>);
+
+#[derive(Resource, Default)]
+pub struct LabelHeatmap {
+    /// Points of the selected classes per frame, empty until all label files are read.
+    pub counts: Vec<u32>,
+    source: Option<HeatmapSource>,
+    task: Option<Task<Vec<u32>>>,
+    progress: Arc<CountProgress>,
+}
+
+/// Shared with the counting task, which reads the files synchronously and is not stopped by dropping it.
+#[derive(Default)]
+struct CountProgress {
+    read_files: AtomicUsize,
+    file_count: usize,
+    cancelled: AtomicBool,
+}
+
+impl LabelHeatmap {
+    /// Share of the read label files while the counts are computed.
+    pub fn progress(&self) -> Option<f32> {
+        self.task.as_ref()?;
+        let read_files = self.progress.read_files.load(Ordering::Relaxed);
+        Some(read_files as f32 / self.progress.file_count.max(1) as f32)
+    }
+}
+
+fn count_labels(
+    label_paths: Vec<PathBuf>,
+    classes: Vec<u16>,
+    progress: Arc<CountProgress>,
+) -> Vec<u32> {
+    let mut counts = Vec::with_capacity(label_paths.len());
+    for labels_path in label_paths {
+        if progress.cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        // unreadable files count as frames without the classes
+        let count = read_labels(labels_path).map_or(0, |labels| {
+            labels
+                .iter()
+                .filter(|label| classes.binary_search(&label.label).is_ok())
+                .count() as u32
+        });
+        progress.read_files.fetch_add(1, Ordering::Relaxed);
+        counts.push(count);
+    }
+    counts
+}
+
+fn update_label_heatmap(
+    player: Res<PlayerState>,
+    config: Res<PlayerConfig>,
+    mut heatmap: ResMut<LabelHeatmap>,
+) {
+    let classes: Vec<u16> = config
+        .persistent
+        .label_map
+        .iter()
+        .filter(|(_, info)| info.heatmap)
+        .map(|(label, _)| *label)
+        .collect();
+    // compared without copying the ids every frame, a comparison sequence does not change them
+    let source = match (player.get_label_folder(), player.get_frame_ids()) {
+        (Some(label_folder), Some(frame_ids)) if !classes.is_empty() => {
+            Some((label_folder, frame_ids, classes.as_slice()))
+        }
+        _ => None,
+    };
+    let counted_source = heatmap
+        .source
+        .as_ref()
+        .map(|(folder, ids, classes)| (folder.as_path(), ids.as_slice(), classes.as_slice()));
+    if source != counted_source {
+        heatmap.progress.cancelled.store(true, Ordering::Relaxed);
+        heatmap.counts.clear();
+        heatmap.task = None;
+        heatmap.source = source
+            .map(|(folder, ids, classes)| (folder.to_path_buf(), ids.to_vec(), classes.to_vec()));
+        if let (Some(label_paths), false) = (player.get_label_paths(), classes.is_empty()) {
+            let progress = Arc::new(CountProgress {
+                file_count: label_paths.len(),
+                ..default()
+            });
+            heatmap.progress = progress.clone();
+            // a long running task, the io pool stays free for buffering frames
+            heatmap.task = Some(
+                AsyncComputeTaskPool::get()
+                    .spawn(async move { count_labels(label_paths, classes, progress) }),
+            );
+        }
+    }
+    if let Some(task) = &mut heatmap.task {
+        if let Some(counts) = future::block_on(future::poll_once(task)) {
+            heatmap.counts = counts;
+            heatmap.task = None;
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    OpenSequence,
+    OpenLabels,
+    DiscardLabels,
+    Fullscreen,
+    /// Second key for the fullscreen, as an action can only have one key.
+    FullscreenAlternative,
+    Inspector,
+    SwitchCameraMode,
+    FrameAll,
+    ResetCamera,
+    PlayPause,
+    NextFrame,
+    PreviousFrame,
+    SwitchPlaybackMode,
+    SetRepeatStart,
+    SetRepeatEnd,
+    ClearRepeat,
+    /// Slot of a numbered bookmark, 0 is recalled with key 1.
+    RecallBookmark(usize),
+    SaveBookmark(usize),
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Run,
+}
+
+/// Number keys of the bookmark slots.
+const BOOKMARK_KEYS: [KeyCode; Action::BOOKMARK_SLOTS] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+impl Action {
+    /// Numbered bookmarks, recalled with the keys 1 to 9.
+    pub const BOOKMARK_SLOTS: usize = 9;
+
+    /// All actions in the order of the key bindings window.
+    pub fn all() -> impl Iterator<Item = Action> {
+        let commands = [
+            Action::OpenSequence,
+            Action::OpenLabels,
+            Action::DiscardLabels,
+            Action::Fullscreen,
+            Action::FullscreenAlternative,
+            Action::Inspector,
+            Action::SwitchCameraMode,
+            Action::FrameAll,
+            Action::ResetCamera,
+            Action::PlayPause,
+            Action::NextFrame,
+            Action::PreviousFrame,
+            Action::SwitchPlaybackMode,
+            Action::SetRepeatStart,
+            Action::SetRepeatEnd,
+            Action::ClearRepeat,
+        ];
+        let movement = [
+            Action::MoveForward,
+            Action::MoveBack,
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::Run,
+        ];
+        commands
+            .into_iter()
+            .chain((0..Self::BOOKMARK_SLOTS).map(Action::RecallBookmark))
+            .chain((0..Self::BOOKMARK_SLOTS).map(Action::SaveBookmark))
+            .chain(movement)
+    }
+    pub fn name(&self) -> Cow<'static, str> {
+        let name = match self {
+            Action::OpenSequence => "Open Sequence Folder",
+            Action::OpenLabels => "Open Label Folder",
+            Action::DiscardLabels => "Discard Labels",
+            Action::Fullscreen => "Fullscreen",
+            Action::FullscreenAlternative => "Fullscreen (Second Key)",
+            Action::Inspector => "Inspector",
+            Action::SwitchCameraMode => "Switch Camera Mode",
+            Action::FrameAll => "Frame All",
+            Action::ResetCamera => "Reset Camera",
+            Action::PlayPause => "Play | Pause",
+            Action::NextFrame => "Next Frame",
+            Action::PreviousFrame => "Previous Frame",
+            Action::SwitchPlaybackMode => "Switch Playback Mode",
+            Action::SetRepeatStart => "Set Repeat Start (A)",
+            Action::SetRepeatEnd => "Set Repeat End (B)",
+            Action::ClearRepeat => "Clear Repeat Range",
+            Action::RecallBookmark(slot) => return format!("Recall Bookmark {}", slot + 1).into(),
+            Action::SaveBookmark(slot) => return format!("Save Bookmark {}", slot + 1).into(),
+            Action::MoveForward => "Move Forward",
+            Action::MoveBack => "Move Backwards",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::Run => "Speedup Movement",
+        };
+        name.into()
+    }
+    /// Camera actions are held together with other keys, so their modifiers are ignored.
+    fn is_held(&self) -> bool {
+        matches!(
+            self,
+            Action::MoveForward
+                | Action::MoveBack
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveUp
+                | Action::MoveDown
+                | Action::Run
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub control: bool,
+}
+
+impl KeyBinding {
+    pub const fn key(key: KeyCode) -> Self {
+        Self {
+            key,
+            shift: false,
+            control: false,
+        }
+    }
+    pub const fn shift(key: KeyCode) -> Self {
+        Self {
+            key,
+            shift: true,
+            control: false,
+        }
+    }
+    pub const fn control(key: KeyCode) -> Self {
+        Self {
+            key,
+            shift: false,
+            control: true,
+        }
+    }
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.control {
+            label.push_str("Ctrl+");
+        }
+        if self.shift {
+            label.push_str("Shift+");
+        }
+        label.push_str(&format!("{:?}", self.key));
+        label
+    }
+    fn modifiers_match(&self, input: &Input<KeyCode>) -> bool {
+        self.shift == input.any_pressed([KeyCode::LShift, KeyCode::RShift])
+            && self.control == input.any_pressed([KeyCode::LControl, KeyCode::RControl])
+    }
+}
+
+/// Keys of all actions, actions missing in the config file use their default key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyBindings(BTreeMap<Action, KeyBinding>);
+
+impl KeyBindings {
+    pub fn default_binding(action: Action) -> KeyBinding {
+        match action {
+            Action::OpenSequence => KeyBinding::key(KeyCode::O),
+            Action::OpenLabels => KeyBinding::key(KeyCode::L),
+            Action::DiscardLabels => KeyBinding::shift(KeyCode::L),
+            Action::Fullscreen => KeyBinding::key(KeyCode::F),
+            Action::FullscreenAlternative => KeyBinding::key(KeyCode::F12),
+            Action::Inspector => KeyBinding::key(KeyCode::I),
+            Action::SwitchCameraMode => KeyBinding::key(KeyCode::C),
+            Action::FrameAll => KeyBinding::key(KeyCode::Home),
+            Action::ResetCamera => KeyBinding::key(KeyCode::R),
+            Action::PlayPause => KeyBinding::key(KeyCode::Space),
+            Action::NextFrame => KeyBinding::key(KeyCode::Right),
+            Action::PreviousFrame => KeyBinding::key(KeyCode::Left),
+            Action::SwitchPlaybackMode => KeyBinding::key(KeyCode::P),
+            Action::SetRepeatStart => KeyBinding::key(KeyCode::LBracket),
+            Action::SetRepeatEnd => KeyBinding::key(KeyCode::RBracket),
+            Action::ClearRepeat => KeyBinding::key(KeyCode::Backslash),
+            Action::RecallBookmark(slot) => {
+                KeyBinding::key(BOOKMARK_KEYS[slot % BOOKMARK_KEYS.len()])
+            }
+            Action::SaveBookmark(slot) => {
+                KeyBinding::control(BOOKMARK_KEYS[slot % BOOKMARK_KEYS.len()])
+            }
+            Action::MoveForward => KeyBinding::key(KeyCode::W),
+            Action::MoveBack => KeyBinding::key(KeyCode::S),
+            Action::MoveLeft => KeyBinding::key(KeyCode::A),
+            Action::MoveRight => KeyBinding::key(KeyCode::D),
+            Action::MoveUp => KeyBinding::key(KeyCode::E),
+            Action::MoveDown => KeyBinding::key(KeyCode::Q),
+            Action::Run => KeyBinding::key(KeyCode::LShift),
+        }
+    }
+    pub fn get(&self, action: Action) -> KeyBinding {
+        self.0
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| Self::default_binding(action))
+    }
+    pub fn set(&mut self, action: Action, binding: KeyBinding) {
+        self.0.insert(action, binding);
+    }
+    pub fn reset(&mut self) {
+        self.0.clear();
+    }
+    pub fn label(&self, action: Action) -> String {
+        self.get(action).label()
+    }
+    pub fn pressed(&self, action: Action, input: &Input<KeyCode>) -> bool {
+        let binding = self.get(action);
+        input.pressed(binding.key) && (action.is_held() || binding.modifiers_match(input))
+    }
+    pub fn just_pressed(&self, action: Action, input: &Input<KeyCode>) -> bool {
+        let binding = self.get(action);
+        input.just_pressed(binding.key) && (action.is_held() || binding.modifiers_match(input))
+    }
+    /// Other actions triggered by the same keys.
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let binding = self.get(action);
+        Action::all()
+            .filter(|other| *other != action)
+            .filter(|other| {
+                let other_binding = self.get(*other);
+                other_binding.key == binding.key
+                    && (other_binding == binding || action.is_held() || other.is_held())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,385 @@
+// Eye-dome lighting, a screen space shading of the depth buffer to improve the depth perception of unlit points
+
+use bevy::{
+    core_pipeline::{
+        core_3d::{self, Camera3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        prepass::DepthPrepass,
+    },
+    ecs::query::QueryState,
+    prelude::*,
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
+        render_resource::*,
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::{BevyDefault, CachedTexture, TextureCache},
+        view::{ExtractedView, ViewDepthTexture, ViewTarget},
+        RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+use bytemuck::{Pod, Zeroable};
+
+use super::instancing::PointColorMap;
+use crate::plugins::PlayerConfig;
+
+pub struct EyeDomeLightingPlugin;
+
+impl Plugin for EyeDomeLightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ExtractComponentPlugin::<EyeDomeLighting>::default())
+            .add_system(update_eye_dome_lighting);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<EyeDomeLightingPipeline>()
+            .init_resource::<SpecializedRenderPipelines<EyeDomeLightingPipeline>>()
+            .init_resource::<EyeDomeLightingUniforms>()
+            .add_system(prepare_eye_dome_lighting.in_set(RenderSet::Prepare));
+
+        let node = EyeDomeLightingNode::new(&mut render_app.world);
+        let mut binding = render_app.world.resource_mut::<RenderGraph>();
+        let graph = binding.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+        graph.add_node(EyeDomeLightingNode::NAME, node);
+        graph.add_slot_edge(
+            graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            EyeDomeLightingNode::NAME,
+            EyeDomeLightingNode::IN_VIEW,
+        );
+        graph.add_node_edge(core_3d::graph::node::MAIN_PASS, EyeDomeLightingNode::NAME);
+        graph.add_node_edge(EyeDomeLightingNode::NAME, core_3d::graph::node::TONEMAPPING);
+    }
+}
+
+#[derive(Component, ExtractComponent, Clone, Copy, PartialEq)]
+pub struct EyeDomeLighting {
+    pub strength: f32,
+    /// Distance of the sampled neighbours in pixels.
+    pub radius: f32,
+}
+
+/// Adds the eye-dome lighting and the depth prepass it reads from to all 3d cameras.
+/// Translucent points are not written to the depth buffer, the shading is off while they are drawn.
+fn update_eye_dome_lighting(
+    mut commands: Commands,
+    config: Res<PlayerConfig>,
+    cameras: Query<(Entity, Option<&EyeDomeLighting>), With<Camera3d>>,
+    added_cameras: Query<(), Added<Camera3d>>,
+) {
+    if !config.is_changed() && added_cameras.is_empty() {
+        return;
+    }
+    let enabled =
+        config.persistent.edl_enabled && !PointColorMap::from_config(&config).is_translucent();
+    for (entity, current) in &cameras {
+        if !enabled {
+            if current.is_some() {
+                commands
+                    .entity(entity)
+                    .remove::<(EyeDomeLighting, DepthPrepass)>();
+            }
+            continue;
+        }
+        let eye_dome_lighting = EyeDomeLighting {
+            strength: config.persistent.edl_strength,
+            radius: config.persistent.edl_radius,
+        };
+        if current != Some(&eye_dome_lighting) {
+            // the prepass makes the depth texture of the main pass copyable
+            commands
+                .entity(entity)
+                .insert((eye_dome_lighting, DepthPrepass));
+        }
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct EyeDomeLightingUniform {
+    inverse_projection: Mat4,
+    /// Origin and size of the views viewport in pixels.
+    viewport: Vec4,
+    strength: f32,
+    radius: f32,
+    _padding: [f32; 2],
+}
+
+/// Uniform buffer of each view, rewritten every frame instead of allocated.
+#[derive(Resource, Default)]
+struct EyeDomeLightingUniforms(HashMap<Entity, Buffer>);
+
+#[derive(Resource)]
+pub struct EyeDomeLightingPipeline {
+    shader: Handle<Shader>,
+    layout: BindGroupLayout,
+    multisampled_layout: BindGroupLayout,
+}
+
+impl FromWorld for EyeDomeLightingPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let shader = asset_server.load("shaders/eye_dome_lighting.wgsl");
+
+        let render_device = world.resource::<RenderDevice>();
+        let create_layout = |multisampled: bool| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("eye dome lighting layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: BufferSize::new(std::mem::size_of::<
+                                EyeDomeLightingUniform,
+                            >()
+                                as u64),
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        EyeDomeLightingPipeline {
+            shader,
+            layout: create_layout(false),
+            multisampled_layout: create_layout(true),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct EyeDomeLightingPipelineKey {
+    texture_format: TextureFormat,
+    multisampled: bool,
+}
+
+impl SpecializedRenderPipeline for EyeDomeLightingPipeline {
+    type Key = EyeDomeLightingPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        let layout = match key.multisampled {
+            true => {
+                shader_defs.push("MULTISAMPLED".into());
+                self.multisampled_layout.clone()
+            }
+            false => self.layout.clone(),
+        };
+        RenderPipelineDescriptor {
+            label: Some("eye dome lighting pipeline".into()),
+            layout: vec![layout],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ViewEyeDomeLighting {
+    pipeline_id: CachedRenderPipelineId,
+    multisampled: bool,
+    /// Sampleable copy of the views depth texture.
+    depth: CachedTexture,
+    uniform: Buffer,
+}
+
+fn prepare_eye_dome_lighting(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<EyeDomeLightingPipeline>>,
+    eye_dome_lighting_pipeline: Res<EyeDomeLightingPipeline>,
+    (mut texture_cache, mut uniforms): (ResMut<TextureCache>, ResMut<EyeDomeLightingUniforms>),
+    (render_device, render_queue): (Res<RenderDevice>, Res<RenderQueue>),
+    views: Query<(Entity, &ExtractedView, &ViewDepthTexture, &EyeDomeLighting)>,
+) {
+    uniforms.0.retain(|entity, _| views.contains(*entity));
+    for (entity, view, view_depth, eye_dome_lighting) in &views {
+        let pipeline_id = pipelines.specialize(
+            &pipeline_cache,
+            &eye_dome_lighting_pipeline,
+            EyeDomeLightingPipelineKey {
+                texture_format: match view.hdr {
+                    true => ViewTarget::TEXTURE_FORMAT_HDR,
+                    false => TextureFormat::bevy_default(),
+                },
+                multisampled: view_depth.texture.sample_count() > 1,
+            },
+        );
+        let depth = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("eye dome lighting depth texture"),
+                size: view_depth.texture.size(),
+                mip_level_count: 1,
+                sample_count: view_depth.texture.sample_count(),
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Depth32Float,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+        );
+        let uniform = EyeDomeLightingUniform {
+            inverse_projection: view.projection.inverse(),
+            viewport: view.viewport.as_vec4(),
+            strength: eye_dome_lighting.strength,
+            radius: eye_dome_lighting.radius,
+            _padding: [0.0; 2],
+        };
+        let uniform_buffer = uniforms.0.entry(entity).or_insert_with(|| {
+            render_device.create_buffer(&BufferDescriptor {
+                label: Some("eye dome lighting uniform buffer"),
+                size: std::mem::size_of::<EyeDomeLightingUniform>() as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+        render_queue.write_buffer(uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+        let uniform = uniform_buffer.clone();
+        commands.entity(entity).insert(ViewEyeDomeLighting {
+            pipeline_id,
+            multisampled: view_depth.texture.sample_count() > 1,
+            depth,
+            uniform,
+        });
+    }
+}
+
+pub struct EyeDomeLightingNode {
+    query: QueryState<(
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static ViewEyeDomeLighting,
+    )>,
+}
+
+impl EyeDomeLightingNode {
+    pub const NAME: &'static str = "eye_dome_lighting";
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for EyeDomeLightingNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((target, view_depth, eye_dome_lighting)) = self.query.get_manual(world, view_entity)
+        else {
+            return Ok(());
+        };
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(eye_dome_lighting.pipeline_id)
+        else {
+            return Ok(());
+        };
+        let eye_dome_lighting_pipeline = world.resource::<EyeDomeLightingPipeline>();
+
+        let depth_texture = &eye_dome_lighting.depth.texture;
+        render_context.command_encoder().copy_texture_to_texture(
+            view_depth.texture.as_image_copy(),
+            depth_texture.as_image_copy(),
+            depth_texture.size(),
+        );
+
+        let post_process = target.post_process_write();
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("eye dome lighting bind group"),
+                layout: match eye_dome_lighting.multisampled {
+                    true => &eye_dome_lighting_pipeline.multisampled_layout,
+                    false => &eye_dome_lighting_pipeline.layout,
+                },
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(post_process.source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(
+                            &eye_dome_lighting.depth.default_view,
+                        ),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: eye_dome_lighting.uniform.as_entire_binding(),
+                    },
+                ],
+            });
+
+        let mut render_pass =
+            render_context
+                .command_encoder()
+                .begin_render_pass(&RenderPassDescriptor {
+                    label: Some("eye_dome_lighting_pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: post_process.destination,
+                        resolve_target: None,
+                        ops: Operations::default(),
+                    })],
+                    depth_stencil_attachment: None,
+                });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@ use bevy::{
     prelude::*,
     render::{
         extract_component::{ExtractComponent, ExtractComponentPlugin},
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
         mesh::{GpuBufferInfo, MeshVertexBufferLayout},
         render_asset::RenderAssets,
         render_phase::{
@@ -17,24 +18,34 @@ use bevy::{
             SetItemPipeline, TrackedRenderPass, RenderCommand, PhaseItem,
         },
         render_resource::*,
-        renderer::RenderDevice,
-        view::{ExtractedView},
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, VisibleEntities},
         RenderApp, RenderSet,
     },
+    utils::HashMap,
 };
 use bytemuck::{Pod, Zeroable};
 
+use crate::plugins::{ColorMode, PlayerConfig};
+
 
 #[derive(Component, Deref)]
 pub struct InstanceMaterialData(pub Vec<InstanceData>);
 
+/// Change tick of the extracted instances, the render world only sees fresh copies of them.
+#[derive(Component, Clone, Copy, PartialEq)]
+pub struct InstanceDataVersion(u32);
+
 impl ExtractComponent for InstanceMaterialData {
-    type Query = &'static InstanceMaterialData;
+    type Query = Ref<'static, InstanceMaterialData>;
     type Filter = ();
-    type Out = Self;
+    type Out = (Self, InstanceDataVersion);
 
-    fn extract_component(item: QueryItem<'_, Self::Query>) -> Option<Self> {
-        Some(InstanceMaterialData(item.0.clone()))
+    fn extract_component(item: QueryItem<'_, Self::Query>) -> Option<Self::Out> {
+        Some((
+            InstanceMaterialData(item.0.clone()),
+            InstanceDataVersion(item.last_changed()),
+        ))
     }
 }
 
@@ -42,33 +53,187 @@ pub struct InstancingPlugin;
 
 impl Plugin for InstancingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(ExtractComponentPlugin::<InstanceMaterialData>::default());
+        app.init_resource::<PointColorMap>()
+            .init_resource::<PointSize>()
+            .add_plugin(ExtractComponentPlugin::<InstanceMaterialData>::default())
+            .add_plugin(ExtractResourcePlugin::<PointColorMap>::default())
+            .add_plugin(ExtractResourcePlugin::<PointSize>::default());
         app.sub_app_mut(RenderApp)
             .add_render_command::<Transparent3d, DrawCustom>()
             .init_resource::<CustomPipeline>()
             .init_resource::<SpecializedMeshPipelines<CustomPipeline>>()
+            .init_resource::<InstanceBufferCache>()
             .add_system(queue_custom.in_set(RenderSet::Queue))
-            .add_system(prepare_instance_buffers.in_set(RenderSet::Prepare));
+            .add_system(prepare_instance_buffers.in_set(RenderSet::Prepare))
+            .add_system(prepare_point_bind_group.in_set(RenderSet::Prepare));
     }
 }
 
+/// Raw point as uploaded to the gpu, the color is resolved in the shader.
 #[derive(Clone, Copy, Pod, Zeroable)]
 #[repr(C)]
 pub struct InstanceData {
     pub position: Vec3,
-    pub scale: f32,
-    pub color: [f32; 4],
+    pub remission: f32,
+    pub label: u32,
+}
+
+impl InstanceData {
+    /// Label id of points without a label file, always drawn with the default color.
+    pub const NO_LABEL: u32 = u32::MAX;
+}
+
+/// Label to color lookup table used by the shader to color the points.
+#[derive(Resource, ExtractResource, Clone, PartialEq, Default)]
+pub struct PointColorMap {
+    pub default_color: [f32; 4],
+    pub color_mode: ColorMode,
+    pub opacity: f32,
+    /// Indexed by label id, labels outside the table use the default color.
+    pub colors: Vec<[f32; 4]>,
+}
+
+impl PointColorMap {
+    pub fn from_config(config: &PlayerConfig) -> Self {
+        let table_size = config
+            .actual_color_map
+            .keys()
+            .max()
+            .map_or(0, |max_label| *max_label as usize + 1);
+        let opacity = config.persistent.point_opacity.clamp(0.0, 1.0);
+        let with_alpha = |color: [f32; 4], alpha: f32| [color[0], color[1], color[2], alpha];
+        let default_color = with_alpha(config.default_color, opacity);
+        let mut colors = vec![default_color; table_size];
+        for (label, color) in &config.actual_color_map {
+            let hidden =
+                matches!(config.persistent.label_map.get(label), Some(info) if info.hidden);
+            let alpha = match hidden {
+                true => opacity * config.persistent.hidden_label_opacity.clamp(0.0, 1.0),
+                false => opacity,
+            };
+            colors[*label as usize] = with_alpha(*color, alpha);
+        }
+        Self {
+            default_color,
+            color_mode: config.persistent.color_mode,
+            opacity,
+            colors,
+        }
+    }
+    /// Translucent points need alpha blending and a back to front order.
+    pub fn is_translucent(&self) -> bool {
+        match self.color_mode {
+            ColorMode::Label => {
+                self.default_color[3] < 1.0 || self.colors.iter().any(|color| color[3] < 1.0)
+            }
+            ColorMode::Remission => self.opacity < 1.0,
+        }
+    }
+}
+
+/// On screen size of the points.
+#[derive(Resource, ExtractResource, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct PointSize {
+    /// Edge length of a point in meters.
+    pub size: f32,
+    pub min_pixels: f32,
+    pub max_pixels: f32,
+    /// The on screen size is kept between `min_pixels` and `max_pixels` if set, otherwise points keep their size in meters.
+    pub attenuation: u32,
+}
+
+impl PointSize {
+    pub fn from_config(config: &PlayerConfig) -> Self {
+        let min_pixels = config.persistent.min_point_pixels.max(0.0);
+        Self {
+            size: config.persistent.point_size,
+            min_pixels,
+            max_pixels: config.persistent.max_point_pixels.max(min_pixels),
+            attenuation: config.persistent.size_attenuation as u32,
+        }
+    }
+}
+
+/// Header of the color map storage buffer, followed by the color table.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct GpuPointColorsHeader {
+    default_color: [f32; 4],
+    color_mode: u32,
+    opacity: f32,
+    _padding: [u32; 2],
+}
+
+#[derive(Resource)]
+pub struct PointBindGroup {
+    bind_group: BindGroup,
+}
+
+fn prepare_point_bind_group(
+    mut commands: Commands,
+    color_map: Res<PointColorMap>,
+    point_size: Res<PointSize>,
+    bind_group: Option<Res<PointBindGroup>>,
+    custom_pipeline: Res<CustomPipeline>,
+    render_device: Res<RenderDevice>,
+) {
+    if bind_group.is_some() && !color_map.is_changed() && !point_size.is_changed() {
+        return;
+    }
+    let header = GpuPointColorsHeader {
+        default_color: color_map.default_color,
+        color_mode: color_map.color_mode as u32,
+        opacity: color_map.opacity,
+        _padding: [0; 2],
+    };
+    let mut contents = bytemuck::bytes_of(&header).to_vec();
+    contents.extend_from_slice(bytemuck::cast_slice(color_map.colors.as_slice()));
+    // an empty runtime sized array is not a valid binding
+    if color_map.colors.is_empty() {
+        contents.extend_from_slice(bytemuck::bytes_of(&color_map.default_color));
+    }
+    let color_map_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("point color map buffer"),
+        contents: &contents,
+        usage: BufferUsages::STORAGE,
+    });
+    let point_size_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("point size buffer"),
+        contents: bytemuck::bytes_of(&*point_size),
+        usage: BufferUsages::UNIFORM,
+    });
+    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+        label: Some("point bind group"),
+        layout: &custom_pipeline.point_layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: color_map_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: point_size_buffer.as_entire_binding(),
+            },
+        ],
+    });
+    commands.insert_resource(PointBindGroup { bind_group });
 }
 
 fn queue_custom(
     transparent_3d_draw_functions: Res<DrawFunctions<Transparent3d>>,
     custom_pipeline: Res<CustomPipeline>,
     msaa: Res<Msaa>,
+    color_map: Res<PointColorMap>,
     mut pipelines: ResMut<SpecializedMeshPipelines<CustomPipeline>>,
     mut pipeline_cache: ResMut<PipelineCache>,
     meshes: Res<RenderAssets<Mesh>>,
     material_meshes: Query<(Entity, &MeshUniform, &Handle<Mesh>), With<InstanceMaterialData>>,
-    mut views: Query<(&ExtractedView, &mut RenderPhase<Transparent3d>)>,
+    mut views: Query<(
+        &ExtractedView,
+        &VisibleEntities,
+        &mut RenderPhase<Transparent3d>,
+    )>,
 ) {
     let draw_custom = transparent_3d_draw_functions
         .read()
@@ -76,11 +241,20 @@ fn queue_custom(
         .unwrap();
 
     let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+    let blend_key = match color_map.is_translucent() {
+        true => MeshPipelineKey::BLEND_ALPHA,
+        false => MeshPipelineKey::BLEND_OPAQUE,
+    };
 
-    for (view, mut transparent_phase) in &mut views {
-        let view_key = msaa_key | MeshPipelineKey::from_hdr(view.hdr);
+    for (view, visible_entities, mut transparent_phase) in &mut views {
+        let view_key = msaa_key | blend_key | MeshPipelineKey::from_hdr(view.hdr);
         let rangefinder = view.rangefinder3d();
-        for (entity, mesh_uniform, mesh_handle) in &material_meshes {
+        // the render layers decide which sequence a view shows
+        let visible_meshes = visible_entities
+            .entities
+            .iter()
+            .filter_map(|entity| material_meshes.get(*entity).ok());
+        for (entity, mesh_uniform, mesh_handle) in visible_meshes {
             if let Some(mesh) = meshes.get(mesh_handle) {
                 let key =
                     view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
@@ -101,22 +275,181 @@ fn queue_custom(
 #[derive(Component)]
 pub struct InstanceBuffer {
     buffer: Buffer,
+    /// Back to front sorted copies of the instances for each view, only used for translucent points.
+    view_buffers: HashMap<Entity, Buffer>,
     length: usize,
 }
 
+impl InstanceBuffer {
+    fn for_view(&self, view: Entity) -> &Buffer {
+        self.view_buffers.get(&view).unwrap_or(&self.buffer)
+    }
+}
+
+/// Gpu buffer that is only reallocated when the instances outgrow it.
+struct ReusableBuffer {
+    buffer: Buffer,
+    capacity: usize,
+}
+
+impl ReusableBuffer {
+    fn write(
+        reusable: &mut Option<ReusableBuffer>,
+        instances: &[InstanceData],
+        label: &'static str,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+    ) -> Buffer {
+        match reusable {
+            Some(reusable) if reusable.capacity >= instances.len() => {
+                render_queue.write_buffer(&reusable.buffer, 0, bytemuck::cast_slice(instances));
+                reusable.buffer.clone()
+            }
+            _ => {
+                // room to grow, the point count differs slightly between frames
+                let capacity = instances.len().max(1).next_power_of_two();
+                let buffer = render_device.create_buffer(&BufferDescriptor {
+                    label: Some(label),
+                    size: (capacity * std::mem::size_of::<InstanceData>()) as u64,
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                render_queue.write_buffer(&buffer, 0, bytemuck::cast_slice(instances));
+                *reusable = Some(ReusableBuffer {
+                    buffer: buffer.clone(),
+                    capacity,
+                });
+                buffer
+            }
+        }
+    }
+}
+
+/// Everything the back to front order of the instances depends on.
+#[derive(Clone, Copy, PartialEq)]
+struct SortKey {
+    version: InstanceDataVersion,
+    view: Mat4,
+    world_to_local: Mat4,
+}
+
+/// Instances of one view, sorted again only if the view or the instances moved.
+#[derive(Default)]
+struct SortedInstances {
+    key: Option<SortKey>,
+    buffer: Option<ReusableBuffer>,
+    depths: Vec<(f32, u32)>,
+    sorted: Vec<InstanceData>,
+}
+
+impl SortedInstances {
+    fn sort_back_to_front(
+        &mut self,
+        instance_data: &[InstanceData],
+        view: &ExtractedView,
+        world_to_local: Mat4,
+    ) {
+        // sort along the view direction, which also works for orthographic projections
+        let view_position = world_to_local.transform_point3(view.transform.translation());
+        let view_forward = world_to_local
+            .transform_vector3(view.transform.forward())
+            .normalize_or_zero();
+        self.depths.clear();
+        self.depths
+            .extend(instance_data.iter().enumerate().map(|(index, instance)| {
+                (
+                    (instance.position - view_position).dot(view_forward),
+                    index as u32,
+                )
+            }));
+        self.depths
+            .sort_unstable_by(|(a, _), (b, _)| b.total_cmp(a));
+        self.sorted.clear();
+        self.sorted.extend(
+            self.depths
+                .iter()
+                .map(|(_, index)| instance_data[*index as usize]),
+        );
+    }
+}
+
+#[derive(Default)]
+struct CachedInstanceBuffers {
+    version: Option<InstanceDataVersion>,
+    buffer: Option<ReusableBuffer>,
+    views: HashMap<Entity, SortedInstances>,
+}
+
+/// Instance buffers of the last frame, the render world entities keep the ids of the main world.
+#[derive(Resource, Default)]
+struct InstanceBufferCache(HashMap<Entity, CachedInstanceBuffers>);
+
 fn prepare_instance_buffers(
     mut commands: Commands,
-    query: Query<(Entity, &InstanceMaterialData)>,
+    query: Query<(
+        Entity,
+        &InstanceMaterialData,
+        &InstanceDataVersion,
+        &MeshUniform,
+    )>,
+    views: Query<(Entity, &ExtractedView), With<RenderPhase<Transparent3d>>>,
+    color_map: Res<PointColorMap>,
+    mut cache: ResMut<InstanceBufferCache>,
     render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
 ) {
-    for (entity, instance_data) in &query {
-        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
-            label: Some("instance data buffer"),
-            contents: bytemuck::cast_slice(instance_data.as_slice()),
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-        });
+    cache.0.retain(|entity, _| query.contains(*entity));
+    let translucent = color_map.is_translucent();
+    for (entity, instance_data, version, mesh_uniform) in &query {
+        let cached = cache.0.entry(entity).or_default();
+        let buffer = match (&cached.buffer, cached.version == Some(*version)) {
+            (Some(reusable), true) => reusable.buffer.clone(),
+            _ => {
+                cached.version = Some(*version);
+                ReusableBuffer::write(
+                    &mut cached.buffer,
+                    instance_data.as_slice(),
+                    "instance data buffer",
+                    &render_device,
+                    &render_queue,
+                )
+            }
+        };
+        let mut view_buffers = HashMap::default();
+        if translucent {
+            let world_to_local = mesh_uniform.transform.inverse();
+            cached
+                .views
+                .retain(|view_entity, _| views.contains(*view_entity));
+            for (view_entity, view) in &views {
+                let sorted = cached.views.entry(view_entity).or_default();
+                let key = SortKey {
+                    version: *version,
+                    view: view.transform.compute_matrix(),
+                    world_to_local,
+                };
+                let view_buffer = match (&sorted.buffer, sorted.key == Some(key)) {
+                    (Some(reusable), true) => reusable.buffer.clone(),
+                    _ => {
+                        sorted.key = Some(key);
+                        sorted.sort_back_to_front(instance_data, view, world_to_local);
+                        ReusableBuffer::write(
+                            &mut sorted.buffer,
+                            sorted.sorted.as_slice(),
+                            "sorted instance data buffer",
+                            &render_device,
+                            &render_queue,
+                        )
+                    }
+                };
+                view_buffers.insert(view_entity, view_buffer);
+            }
+        } else {
+            cached.views.clear();
+        }
         commands.entity(entity).insert(InstanceBuffer {
             buffer,
+            view_buffers,
             length: instance_data.len(),
         });
     }
@@ -126,6 +459,7 @@ fn prepare_instance_buffers(
 pub struct CustomPipeline {
     shader: Handle<Shader>,
     mesh_pipeline: MeshPipeline,
+    point_layout: BindGroupLayout,
 }
 
 impl FromWorld for CustomPipeline {
@@ -135,9 +469,40 @@ impl FromWorld for CustomPipeline {
 
         let mesh_pipeline = world.resource::<MeshPipeline>();
 
+        let render_device = world.resource::<RenderDevice>();
+        let point_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("point layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(
+                            std::mem::size_of::<GpuPointColorsHeader>() as u64
+                                + std::mem::size_of::<[f32; 4]>() as u64,
+                        ),
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(std::mem::size_of::<PointSize>() as u64),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
         CustomPipeline {
             shader,
             mesh_pipeline: mesh_pipeline.clone(),
+            point_layout,
         }
     }
 }
@@ -152,6 +517,7 @@ impl SpecializedMeshPipeline for CustomPipeline {
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
         let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
         descriptor.vertex.shader = self.shader.clone();
+        descriptor.layout.push(self.point_layout.clone());
         descriptor.vertex.buffers.push(VertexBufferLayout {
             array_stride: std::mem::size_of::<InstanceData>() as u64,
             step_mode: VertexStepMode::Instance,
@@ -162,7 +528,7 @@ impl SpecializedMeshPipeline for CustomPipeline {
                     shader_location: 3, // shader locations 0-2 are taken up by Position, Normal and UV attributes
                 },
                 VertexAttribute {
-                    format: VertexFormat::Float32x4,
+                    format: VertexFormat::Uint32,
                     offset: VertexFormat::Float32x4.size(),
                     shader_location: 4,
                 },
@@ -177,20 +543,44 @@ type DrawCustom = (
     SetItemPipeline,
     SetMeshViewBindGroup<0>,
     SetMeshBindGroup<1>,
+    SetPointBindGroup<2>,
     DrawMeshInstanced,
 );
 
+pub struct SetPointBindGroup<const I: usize>;
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetPointBindGroup<I> {
+    type Param = Option<SRes<PointBindGroup>>;
+    type ViewWorldQuery = ();
+    type ItemWorldQuery = ();
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        _entity: (),
+        point_bind_group: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(point_bind_group) = point_bind_group else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, &point_bind_group.into_inner().bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
 pub struct DrawMeshInstanced;
 
 impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
     type Param = SRes<RenderAssets<Mesh>>;
-    type ViewWorldQuery = ();
+    type ViewWorldQuery = Entity;
     type ItemWorldQuery = (Read<Handle<Mesh>>, Read<InstanceBuffer>);
 
     #[inline]
     fn render<'w>(
         _item: &P,
-        _view: (),
+        view: Entity,
         (mesh_handle, instance_buffer): (&'w Handle<Mesh>, &'w InstanceBuffer),
         meshes: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
@@ -201,7 +591,7 @@ impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
         };
 
         pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
-        pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
+        pass.set_vertex_buffer(1, instance_buffer.for_view(view).slice(..));
 
         match &gpu_mesh.buffer_info {
             GpuBufferInfo::Indexed {
@@ -1,73 +1,202 @@
-use std::path::PathBuf;
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
 
 use bevy::{
     prelude::*,
-    render::view::NoFrustumCulling,
+    render::view::{Layer, NoFrustumCulling, RenderLayers},
     tasks::{IoTaskPool, Task},
-    utils::HashMap,
+    utils::HashSet,
 };
 use futures_lite::future;
 
 use crate::{
-    io::{self, *},
-    plugins::PlayerConfig,
+    io::*,
+    plugins::{Config, FrameErrorPolicy, Notification, PlaybackMode, PlayerConfig},
 };
 
-use super::instancing::*;
+use super::{eye_dome_lighting::EyeDomeLightingPlugin, instancing::*};
 
 pub struct LidarPlugin;
 
 impl Plugin for LidarPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerState::default())
+            .insert_resource(EgoPose::default())
             .add_startup_system(load_config)
             .add_system(player)
             .add_system(buffer_next_frames)
             .add_system(handle_read_frames_task)
-            .add_plugin(InstancingPlugin);
+            .add_system(update_color_map)
+            .add_system(update_point_size)
+            .add_plugin(InstancingPlugin)
+            .add_plugin(EyeDomeLightingPlugin);
     }
 }
+/// Pose of the sensor for the rendered frame, identity if the points are shown in sensor coordinates.
+#[derive(Resource, Default)]
+pub struct EgoPose(pub Transform);
+
+impl EgoPose {
+    /// Transforms a ray from world coordinates into sensor coordinates.
+    pub fn ray_to_sensor(&self, ray: Ray) -> Ray {
+        let world_to_sensor = self.0.compute_matrix().inverse();
+        Ray {
+            origin: world_to_sensor.transform_point3(ray.origin),
+            direction: world_to_sensor.transform_vector3(ray.direction),
+        }
+    }
+}
+
 struct SpeedSettings {
     sensor_fps: f64,
     time_scale: f64,
 }
 
+/// Index of the sequence shown next to the opened sequence for comparison.
+pub const COMPARISON_SLOT: usize = 1;
+
+/// Render layer of the points of a sequence slot, layer 0 keeps the helpers visible in all viewports.
+pub fn sequence_layer(slot: usize) -> Layer {
+    1 + slot as Layer
+}
+
+/// A sequence with its own frame buffer, all slots share the timeline of the player.
+struct SequenceSlot {
+    sequence: Option<Sequence>,
+    sequence_number: u32,
+    last_rendered_frame: usize,
+    pose: Transform,
+    /// Tick of the last time each frame was loaded or rendered.
+    last_used: Vec<u64>,
+    memory_usage: usize,
+}
+
+impl Default for SequenceSlot {
+    fn default() -> Self {
+        Self {
+            sequence: None,
+            sequence_number: 0,
+            last_rendered_frame: usize::MAX,
+            pose: Transform::IDENTITY,
+            last_used: Vec::new(),
+            memory_usage: 0,
+        }
+    }
+}
+
+impl SequenceSlot {
+    fn insert_frame(&mut self, frame_number: usize, frame: Frame, tick: u64) {
+        if let Some(sequence) = &mut self.sequence {
+            self.memory_usage += frame.memory_size();
+            if let Some(replaced) = sequence.frames[frame_number].replace(frame) {
+                self.memory_usage -= replaced.memory_size();
+            }
+            sequence.load_states[frame_number] = LoadState::Loaded;
+            self.last_used[frame_number] = tick;
+        }
+    }
+    fn evict_frame(&mut self, frame_number: usize) {
+        if let Some(sequence) = &mut self.sequence {
+            if let Some(frame) = sequence.frames[frame_number].take() {
+                self.memory_usage -= frame.memory_size();
+            }
+            sequence.load_states[frame_number] = LoadState::NotRequested;
+        }
+    }
+    fn clear_frames(&mut self) {
+        if let Some(sequence) = &mut self.sequence {
+            for frame in &mut sequence.frames {
+                *frame = None;
+            }
+            for load_state in &mut sequence.load_states {
+                *load_state = LoadState::NotRequested;
+            }
+        }
+        self.memory_usage = 0;
+    }
+    /// Loaded frame that was used the longest time ago, the protected frames are kept for the playback.
+    fn least_recently_used(&self, protected: &[bool]) -> Option<(u64, usize)> {
+        let sequence = self.sequence.as_ref()?;
+        sequence
+            .load_states
+            .iter()
+            .enumerate()
+            .filter(|(iter, load_state)| {
+                **load_state == LoadState::Loaded && !protected.get(*iter).copied().unwrap_or(false)
+            })
+            .map(|(iter, _)| (self.last_used[iter], iter))
+            .min()
+    }
+    /// Number of frames at the start of the playback window that are ready to be shown.
+    fn buffered_frames(&self, window: &[usize]) -> usize {
+        let Some(sequence) = &self.sequence else {
+            return window.len();
+        };
+        window
+            .iter()
+            .take_while(|frame| {
+                matches!(
+                    sequence.load_states[**frame],
+                    LoadState::Loaded | LoadState::Failed
+                )
+            })
+            .count()
+    }
+}
+
 #[derive(Resource)]
 pub struct PlayerState {
     start_time: Option<f64>,
-    sequence: Option<Sequence>,
+    slots: [SequenceSlot; 2],
     sequence_number: u32,
     mesh: Option<Handle<Mesh>>,
     wait_for_buffering: bool,
     actual_frame: usize,
-    buffer_frame: usize,
     max_frame: usize,
     start_frame: usize,
-    last_rendered_frame: usize,
+    /// Frames played since the start frame, negative when playing backward.
+    advanced: i64,
+    /// Ping-pong playback was on its way back to the first frame at the start frame.
+    start_on_return: bool,
     has_frame_request: bool,
     paused: bool,
+    backward: bool,
     speed: SpeedSettings,
+    playback_mode: PlaybackMode,
+    repeat_start: Option<usize>,
+    repeat_end: Option<usize>,
+    /// Bytes the loaded frames of all slots may occupy.
+    memory_budget: usize,
+    tick: u64,
 }
 
 impl Default for PlayerState {
     fn default() -> Self {
         Self {
             start_time: None,
-            sequence: None,
+            slots: Default::default(),
             sequence_number: 0,
             mesh: None,
             wait_for_buffering: false,
             actual_frame: 0,
             start_frame: 0,
-            buffer_frame: 0,
+            advanced: 0,
+            start_on_return: false,
             max_frame: 0,
-            last_rendered_frame: usize::MAX,
             has_frame_request: false,
             paused: true,
+            backward: false,
             speed: SpeedSettings {
                 sensor_fps: 10.0,
                 time_scale: 1.0,
             },
+            playback_mode: PlaybackMode::Once,
+            repeat_start: None,
+            repeat_end: None,
+            memory_budget: Config::DEFAULT_MEMORY_BUDGET_MB as usize * PlayerState::MEGABYTE,
+            tick: 0,
         }
     }
 }
@@ -76,77 +205,319 @@ impl PlayerState {
     const MINIMUM_BUFFERED_FRAMES: usize = 30;
     const MAX_BUFFER_RANGE: usize = 300;
     const BUFFER_SLIDING_WINDOW: usize = 5;
-    const MEMORY_RANGE: usize = 500;
+    pub const MEGABYTE: usize = 1024 * 1024;
     pub fn is_paused(&self) -> bool {
         self.paused
     }
     pub fn get_frame(&self) -> usize {
         self.actual_frame
     }
-    pub fn get_frame_content(&self) -> Option<&Frame> {
-        Some(self.sequence.as_ref()?.frames[self.actual_frame].as_ref()?)
+    /// Frame position including the fraction to the next frame while playing.
+    pub fn get_exact_frame(&self, time_in_seconds: f64) -> f64 {
+        match (self.paused || self.wait_for_buffering, self.start_time) {
+            (false, Some(start_time)) => {
+                let passed_time = time_in_seconds - start_time;
+                let advanced = passed_time * self.speed.sensor_fps * self.speed.time_scale;
+                let frame = self.frame_after(advanced as i64);
+                let next_frame = self.frame_after(advanced as i64 + advanced.signum() as i64);
+                match frame.abs_diff(next_frame) {
+                    // no interpolation across the jump back to the start of a loop
+                    1 => frame as f64 + (next_frame as f64 - frame as f64) * advanced.fract().abs(),
+                    _ => frame as f64,
+                }
+            }
+            _ => self.actual_frame as f64,
+        }
+    }
+    pub fn get_sequence_folder(&self) -> Option<&Path> {
+        Some(self.slots[0].sequence.as_ref()?.folder.as_path())
+    }
+    pub fn get_label_folder(&self) -> Option<&Path> {
+        self.slots[0].sequence.as_ref()?.label_folder.as_deref()
+    }
+    /// Label file of every frame of the opened sequence.
+    pub fn get_label_paths(&self) -> Option<Vec<PathBuf>> {
+        let sequence = self.slots[0].sequence.as_ref()?;
+        (0..sequence.frame_count)
+            .map(|frame| sequence.label_path(frame))
+            .collect()
+    }
+    /// File stem the frame was read from, e.g. `000042` or a timestamp.
+    pub fn get_frame_id(&self, frame: usize) -> Option<&str> {
+        self.slots[0]
+            .sequence
+            .as_ref()?
+            .frame_ids
+            .get(frame)
+            .map(|id| id.as_str())
+    }
+    /// File stems of the frames of the opened sequence.
+    pub fn get_frame_ids(&self) -> Option<&[String]> {
+        Some(self.slots[0].sequence.as_ref()?.frame_ids.as_slice())
+    }
+    /// Frame read from the file with the stem `frame_id`.
+    pub fn get_frame_index(&self, frame_id: &str) -> Option<usize> {
+        self.slots[0]
+            .sequence
+            .as_ref()?
+            .frame_ids
+            .iter()
+            .position(|id| id == frame_id)
+    }
+    pub fn get_frame_content_of(&self, slot: usize) -> Option<&Frame> {
+        self.slots[slot]
+            .sequence
+            .as_ref()?
+            .frames
+            .get(self.actual_frame)?
+            .as_ref()
+    }
+    /// Pose the rendered frame of the slot was placed with.
+    pub fn get_pose_of(&self, slot: usize) -> Transform {
+        self.slots[slot].pose
+    }
+    /// Frames in the order the playback shows them, starting with the actual frame.
+    /// Loops and A–B repeats wrap around, each frame is contained once.
+    fn playback_window(&self) -> Vec<usize> {
+        let step = match self.speed.time_scale < 0.0 {
+            true => -1,
+            false => 1,
+        };
+        let mut contained = vec![false; self.max_frame + 1];
+        let mut window = Vec::new();
+        let frames = std::iter::once(self.actual_frame).chain(
+            (1..PlayerState::MAX_BUFFER_RANGE as i64)
+                .map(|steps| self.frame_after(self.advanced + steps * step)),
+        );
+        for frame in frames {
+            if frame <= self.max_frame && !contained[frame] {
+                contained[frame] = true;
+                window.push(frame);
+            }
+        }
+        window
+    }
+    /// Runs of frames with the same load state, a frame counts as loaded once all sequences have loaded it.
+    pub fn get_load_segments(&self) -> Vec<(RangeInclusive<usize>, LoadState)> {
+        let sequences = self.slots.iter().filter_map(|slot| slot.sequence.as_ref());
+        if sequences.clone().next().is_none() {
+            return Vec::new();
+        }
+        let mut segments: Vec<(RangeInclusive<usize>, LoadState)> = Vec::new();
+        for frame in 0..=self.max_frame {
+            let load_state = sequences
+                .clone()
+                .map(|sequence| sequence.load_states[frame])
+                .fold(LoadState::Loaded, |combined, load_state| {
+                    match (combined, load_state) {
+                        (LoadState::Failed, _) | (_, LoadState::Failed) => LoadState::Failed,
+                        (LoadState::NotRequested, _) | (_, LoadState::NotRequested) => {
+                            LoadState::NotRequested
+                        }
+                        (LoadState::Requested, _) | (_, LoadState::Requested) => {
+                            LoadState::Requested
+                        }
+                        _ => LoadState::Loaded,
+                    }
+                });
+            match segments.last_mut() {
+                Some((frames, last_state)) if *last_state == load_state => {
+                    *frames = *frames.start()..=frame;
+                }
+                _ => segments.push((frame..=frame, load_state)),
+            }
+        }
+        segments
     }
-    pub fn get_buffer_frame(&self) -> usize {
-        self.buffer_frame
+    pub fn has_poses(&self) -> bool {
+        matches!(&self.slots[0].sequence, Some(sequence) if sequence.poses.is_some())
+    }
+    pub fn is_comparing(&self) -> bool {
+        self.slots[COMPARISON_SLOT].sequence.is_some()
     }
     pub fn get_max_frame(&self) -> usize {
         self.max_frame
     }
     pub fn try_set_labels(&mut self, path: PathBuf) -> Result<(), SequenceReadError> {
-        crate::io::is_valid_label_dir(path.clone(), self.max_frame + 1)?;
-        self.set_label_intern(Some(path));
+        let frame_ids = self.slots[0]
+            .sequence
+            .as_ref()
+            .map_or(&[][..], |sequence| &sequence.frame_ids[..]);
+        crate::io::is_valid_label_dir(path.clone(), frame_ids)?;
+        self.set_label_intern(0, Some(path));
         Ok(())
     }
     pub fn discard_labels(&mut self) {
-        self.set_label_intern(None);
+        self.set_label_intern(0, None);
     }
-    fn set_label_intern(&mut self, label: Option<PathBuf>) {
-        if let Some(sequence) = &mut self.sequence {
+    /// Compares the opened sequence with itself using the labels of another folder.
+    pub fn try_set_comparison_labels(&mut self, path: PathBuf) -> Result<(), SequenceReadError> {
+        let Some(sequence) = &self.slots[0].sequence else {
+            return Err(SequenceReadError::FolderDontExist);
+        };
+        crate::io::is_valid_label_dir(path.clone(), &sequence.frame_ids)?;
+        let mut comparison = read_sequence_from_dir(sequence.folder.clone())?;
+        comparison.label_folder = Some(path);
+        self.set_comparison_sequence(comparison);
+        Ok(())
+    }
+    fn set_label_intern(&mut self, slot: usize, label: Option<PathBuf>) {
+        let slot = &mut self.slots[slot];
+        if let Some(sequence) = &mut slot.sequence {
             sequence.label_folder = label;
-            for frame in &mut sequence.frames {
-                *frame = None;
-            }
-            for load_state in &mut sequence.load_states {
-                *load_state = LoadState::NotRequested;
-            }
-            self.last_rendered_frame = usize::MAX;
+            slot.clear_frames();
+            slot.last_rendered_frame = usize::MAX;
             self.request_frame(self.actual_frame);
         }
     }
     pub fn set_time_scale(&mut self, time_scale: f64) {
-        self.speed.time_scale = time_scale.max(0.0);
-        self.start_time = None;
-        self.start_frame = self.actual_frame;
+        // the direction of the ping-pong is taken from the previous time scale
+        self.restart_from_actual_frame();
+        self.speed.time_scale = time_scale;
+    }
+    pub fn get_playback_mode(&self) -> PlaybackMode {
+        self.playback_mode
+    }
+    pub fn set_playback_mode(&mut self, playback_mode: PlaybackMode) {
+        self.playback_mode = playback_mode;
+        self.restart_from_actual_frame();
+    }
+    /// Markers of the A–B range, playback repeats between them as soon as one is set.
+    pub fn get_repeat_markers(&self) -> (Option<usize>, Option<usize>) {
+        (self.repeat_start, self.repeat_end)
+    }
+    pub fn set_repeat_start(&mut self, frame: usize) {
+        let frame = frame.min(self.max_frame);
+        self.repeat_start = Some(frame);
+        if matches!(self.repeat_end, Some(end) if end <= frame) {
+            self.repeat_end = None;
+        }
+        self.restart_from_actual_frame();
+    }
+    pub fn set_repeat_end(&mut self, frame: usize) {
+        let frame = frame.min(self.max_frame);
+        self.repeat_end = Some(frame);
+        if matches!(self.repeat_start, Some(start) if start >= frame) {
+            self.repeat_start = None;
+        }
+        self.restart_from_actual_frame();
+    }
+    pub fn clear_repeat(&mut self) {
+        self.repeat_start = None;
+        self.repeat_end = None;
+        self.restart_from_actual_frame();
+    }
+    pub fn set_memory_budget(&mut self, megabytes: u32) {
+        let range = Config::MEMORY_BUDGET_RANGE_MB;
+        let megabytes = megabytes.clamp(*range.start(), *range.end());
+        self.memory_budget = megabytes as usize * PlayerState::MEGABYTE;
+        self.evict_least_recently_used();
+    }
+    pub fn get_memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+    /// Bytes occupied by the loaded frames of all slots.
+    pub fn get_memory_usage(&self) -> usize {
+        self.slots.iter().map(|slot| slot.memory_usage).sum()
+    }
+    /// Frees the least recently used frames until the loaded frames fit into the memory budget.
+    fn evict_least_recently_used(&mut self) {
+        let protected = self.protected_frames();
+        while self.get_memory_usage() > self.memory_budget {
+            let Some((slot, frame)) = self.least_recently_used(&protected) else {
+                return;
+            };
+            self.slots[slot].evict_frame(frame);
+        }
+    }
+    /// Frames of the playback window, they are shown next and never evicted.
+    fn protected_frames(&self) -> Vec<bool> {
+        let mut protected = vec![false; self.max_frame + 1];
+        for frame in self.playback_window() {
+            protected[frame] = true;
+        }
+        protected
+    }
+    fn least_recently_used(&self, protected: &[bool]) -> Option<(usize, usize)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let (last_used, frame) = slot.least_recently_used(protected)?;
+                Some((last_used, index, frame))
+            })
+            .min()
+            .map(|(_, slot, frame)| (slot, frame))
+    }
+    /// The budget is used up by frames needed for the playback, buffering has to wait.
+    fn is_memory_exhausted(&self) -> bool {
+        self.get_memory_usage() >= self.memory_budget
+            && self.least_recently_used(&self.protected_frames()).is_none()
     }
     pub fn set_sensor_fps(&mut self, fps: f64) {
         self.speed.sensor_fps = fps;
     }
+    pub fn get_sensor_fps(&self) -> f64 {
+        self.speed.sensor_fps
+    }
     pub fn get_time_scale(&self) -> f64 {
         self.speed.time_scale
     }
     pub fn set_mesh(&mut self, mesh: Handle<Mesh>) {
         self.mesh = Some(mesh);
     }
+    /// Opens a sequence and closes the comparison.
     pub fn set_sequence(&mut self, sequence: Sequence) {
-        self.max_frame = sequence.frame_count.saturating_sub(1);
-        self.sequence_number += 1;
-        self.sequence = Some(sequence);
-        self.start_frame = 0;
+        self.slots[COMPARISON_SLOT] = SequenceSlot::default();
         self.actual_frame = 0;
-        self.last_rendered_frame = usize::MAX;
-        self.buffer_frame = 0;
+        self.backward = false;
+        self.restart_from_actual_frame();
         self.paused = true;
+        self.repeat_start = None;
+        self.repeat_end = None;
+        self.set_slot(0, sequence);
+    }
+    /// Shows a second sequence on the same timeline, the timeline ends with the shorter sequence.
+    pub fn set_comparison_sequence(&mut self, sequence: Sequence) {
+        self.set_slot(COMPARISON_SLOT, sequence);
+        self.request_frame(self.actual_frame.min(self.max_frame));
     }
+    pub fn close_comparison(&mut self) {
+        self.slots[COMPARISON_SLOT] = SequenceSlot::default();
+        self.update_max_frame();
+    }
+    fn set_slot(&mut self, slot: usize, sequence: Sequence) {
+        self.sequence_number += 1;
+        self.slots[slot] = SequenceSlot {
+            last_used: vec![0; sequence.frame_count],
+            sequence: Some(sequence),
+            sequence_number: self.sequence_number,
+            ..default()
+        };
+        self.update_max_frame();
+    }
+    fn update_max_frame(&mut self) {
+        self.max_frame = self
+            .slots
+            .iter()
+            .filter_map(|slot| slot.sequence.as_ref())
+            .map(|sequence| sequence.frame_count.saturating_sub(1))
+            .min()
+            .unwrap_or(0);
+        let max_frame = self.max_frame;
+        self.repeat_start = self.repeat_start.filter(|start| *start < max_frame);
+        self.repeat_end = self.repeat_end.map(|end| end.min(max_frame));
+    }
+    /// Frames past the end of the sequence request the last frame.
     pub fn request_frame(&mut self, frame: usize) {
         self.has_frame_request = true;
-        self.start_frame = frame;
-        self.actual_frame = frame;
-        self.buffer_frame = frame;
-        self.start_time = None;
-        self.free_memory_after_request();
+        self.actual_frame = frame.min(self.max_frame);
+        self.restart_from_actual_frame();
     }
     pub fn request_update(&mut self) {
-        self.last_rendered_frame = usize::MAX;
+        for slot in &mut self.slots {
+            slot.last_rendered_frame = usize::MAX;
+        }
     }
     pub fn next_frame(&mut self) {
         self.request_frame(self.actual_frame + 1);
@@ -156,49 +527,59 @@ impl PlayerState {
     }
     pub fn toggle_play(&mut self) {
         self.paused = !self.paused;
-        self.start_frame = self.actual_frame;
-        self.start_time = None;
+        self.restart_from_actual_frame();
     }
     pub fn play(&mut self) {
         self.paused = false;
-        self.start_frame = self.actual_frame;
-        self.start_time = None;
+        self.restart_from_actual_frame();
     }
     pub fn pause(&mut self) {
         self.paused = true;
     }
     fn update(&mut self, time_in_seconds: f64) {
         let passed_time = time_in_seconds - *self.start_time.get_or_insert(time_in_seconds);
-        self.actual_frame = ((passed_time * self.speed.sensor_fps * self.speed.time_scale)
-            as usize
-            + self.start_frame)
-            .min(self.max_frame)
-            .max(0);
+        let advanced = (passed_time * self.speed.sensor_fps * self.speed.time_scale) as i64;
+        let frame = self.frame_after(advanced);
+        let step = if self.speed.time_scale < 0.0 { -1 } else { 1 };
+        let next_frame = self.frame_after(advanced + step);
+        let backward = match frame.abs_diff(next_frame) {
+            1 => next_frame < frame,
+            // at the end of the sequence or before the jump of a loop
+            _ => self.speed.time_scale < 0.0,
+        };
+        self.backward = backward;
+        self.advanced = advanced;
+        self.actual_frame = frame;
     }
-    fn free_memory_after_request(&mut self) {
-        if let Some(sequence) = &mut self.sequence {
-            let min = self.actual_frame.saturating_sub(PlayerState::MEMORY_RANGE);
-            let max = self.actual_frame.saturating_add(PlayerState::MEMORY_RANGE);
-            let memory_range = min..max;
-            sequence
-                .frames
-                .iter_mut()
-                .zip(&mut sequence.load_states)
-                .enumerate()
-                .filter(|(iter, _)| !memory_range.contains(iter))
-                .for_each(|(_, (frame, load_state))| {
-                    *frame = None;
-                    *load_state = LoadState::NotRequested;
-                });
-        }
+    /// The playback continues from the actual frame in its current direction.
+    fn restart_from_actual_frame(&mut self) {
+        self.start_frame = self.actual_frame;
+        self.start_time = None;
+        self.advanced = 0;
+        self.start_on_return = self.backward != (self.speed.time_scale < 0.0);
     }
-    fn free_memory_after_frame_update(&mut self) {
-        if let Some(sequence) = &mut self.sequence {
-            let frame_to_delete = self.actual_frame.saturating_sub(PlayerState::MEMORY_RANGE);
-            if frame_to_delete != 0 {
-                sequence.frames[frame_to_delete] = None;
-                sequence.load_states[frame_to_delete] = LoadState::NotRequested;
+    /// Frame reached after playing `advanced` frames from the start frame, negative when playing backward.
+    fn frame_after(&self, advanced: i64) -> usize {
+        let repeating = self.repeat_start.is_some() || self.repeat_end.is_some();
+        let first = self.repeat_start.unwrap_or(0);
+        let last = self.repeat_end.unwrap_or(self.max_frame).max(first);
+        let length = (last - first) as i64;
+        let start_offset = (self.start_frame.clamp(first, last) - first) as i64;
+        let offset = start_offset + advanced;
+        match (self.playback_mode, repeating) {
+            (PlaybackMode::Once, false) => {
+                (self.start_frame as i64 + advanced).clamp(0, self.max_frame as i64) as usize
+            }
+            (PlaybackMode::PingPong, _) if length > 0 => {
+                // position on the way there and back, the way back starts at `length`
+                let start_position = match self.start_on_return {
+                    true => 2 * length - start_offset,
+                    false => start_offset,
+                };
+                let position = (start_position + advanced).rem_euclid(2 * length);
+                first + position.min(2 * length - position) as usize
             }
+            _ => first + offset.rem_euclid(length + 1) as usize,
         }
     }
 }
@@ -207,180 +588,437 @@ fn load_config(
     mut state: ResMut<PlayerState>,
     mut meshes: ResMut<Assets<Mesh>>,
     config: Res<PlayerConfig>,
+    mut notifications: EventWriter<Notification>,
 ) {
     state.set_sensor_fps(config.persistent.sensor_fps);
+    state.set_memory_budget(config.persistent.memory_budget_mb);
+    state.set_playback_mode(config.persistent.playback_mode);
     state.mesh = Some(meshes.add(Mesh::from(shape::Cube {
         size: config.persistent.point_size,
     })));
     if let Some(file_path) = &config.persistent.folder_path {
         match read_sequence_from_dir(file_path.into()) {
-            Ok(sequence) => state.set_sequence(sequence),
+            Ok(sequence) => {
+                notifications.send_batch(sequence.pose_warning());
+                state.set_sequence(sequence)
+            }
             Err(error) => {
-                rfd::MessageDialog::new()
-                    .set_title("Error")
-                    .set_description(&format!("Cannot read folder: {file_path}\n{error}"))
-                    .set_buttons(rfd::MessageButtons::Ok)
-                    .set_level(rfd::MessageLevel::Error)
-                    .show();
+                notifications.send(Notification::error(format!(
+                    "Cannot read folder: {file_path}\n{error}"
+                )));
             }
         }
     }
 }
 
+/// Sequence slot the spawned points belong to.
+#[derive(Component)]
+struct FrameSlot(usize);
+
 fn player(
     mut commands: Commands,
     time: Res<Time>,
     mut state: ResMut<PlayerState>,
     config: Res<PlayerConfig>,
-    query: Query<Entity, With<InstanceMaterialData>>,
+    mut ego_pose: ResMut<EgoPose>,
+    query: Query<(Entity, &FrameSlot), With<InstanceMaterialData>>,
 ) {
     if !state.paused && !state.wait_for_buffering {
         state.update(time.elapsed_seconds_f64());
     }
     if state.wait_for_buffering {
-        state.wait_for_buffering = state.buffer_frame
-            < usize::min(
-                state.actual_frame + PlayerState::MINIMUM_BUFFERED_FRAMES,
-                state.max_frame,
-            );
+        let window = state.playback_window();
+        let needed = window.len().min(PlayerState::MINIMUM_BUFFERED_FRAMES);
+        let buffering = state
+            .slots
+            .iter()
+            .any(|slot| slot.buffered_frames(&window) < needed);
+        // a small memory budget may not hold the minimum buffered frames
+        state.wait_for_buffering = buffering && !state.is_memory_exhausted();
     }
-    if state.last_rendered_frame != state.actual_frame {
-        if let Some(sequence) = &state.sequence {
-            if let Some(frame) = &sequence.frames[state.actual_frame] {
-                //change frame content
-                query.for_each(|entity| commands.entity(entity).despawn());
-                spawn_frame(
-                    &mut commands,
-                    &config,
-                    frame,
-                    state.mesh.as_ref().unwrap().clone(),
-                );
-                state.last_rendered_frame = state.actual_frame;
-            } else {
-                state.wait_for_buffering = true;
-                state.start_time = None;
+    let state = state.as_mut();
+    state.tick += 1;
+    let actual_frame = state.actual_frame;
+    let mut missing_frame = false;
+    let mut failed_frame = false;
+    for (slot_index, slot) in state.slots.iter_mut().enumerate() {
+        let despawn_slot = |commands: &mut Commands| {
+            query
+                .iter()
+                .filter(|(_, frame_slot)| frame_slot.0 == slot_index)
+                .for_each(|(entity, _)| commands.entity(entity).despawn());
+        };
+        let Some(sequence) = &slot.sequence else {
+            // removes the points of a closed comparison
+            despawn_slot(&mut commands);
+            continue;
+        };
+        if slot.last_rendered_frame == actual_frame {
+            continue;
+        }
+        if let Some(frame) = &sequence.frames[actual_frame] {
+            slot.last_used[actual_frame] = state.tick;
+            //change frame content
+            despawn_slot(&mut commands);
+            let pose = match (config.persistent.world_coordinates, &sequence.poses) {
+                (true, Some(poses)) => Transform::from_matrix(poses[actual_frame]),
+                _ => Transform::IDENTITY,
+            };
+            if slot_index == 0 {
+                ego_pose.0 = pose;
             }
+            slot.pose = pose;
+            spawn_frame(
+                &mut commands,
+                &config,
+                frame,
+                (pose, slot_index),
+                state.mesh.as_ref().unwrap().clone(),
+            );
+            slot.last_rendered_frame = actual_frame;
+        } else if sequence.load_states[actual_frame] == LoadState::Failed {
+            // the previous frame stays visible
+            slot.last_rendered_frame = actual_frame;
+            failed_frame = true;
+        } else {
+            missing_frame = true;
         }
-        state.free_memory_after_frame_update();
+    }
+    if failed_frame && config.persistent.frame_error_policy == FrameErrorPolicy::Stop {
+        state.paused = true;
+    }
+    if missing_frame {
+        state.wait_for_buffering = true;
+        state.restart_from_actual_frame();
     }
 }
 
-fn spawn_frame(commands: &mut Commands, config: &PlayerConfig, frame: &Frame, mesh: Handle<Mesh>) {
+fn spawn_frame(
+    commands: &mut Commands,
+    config: &PlayerConfig,
+    frame: &Frame,
+    (pose, slot): (Transform, usize),
+    mesh: Handle<Mesh>,
+) {
+    let labels = frame
+        .labels
+        .iter()
+        .flatten()
+        .map(|label| label.label as u32)
+        .chain(std::iter::repeat(InstanceData::NO_LABEL));
+    let instances = frame
+        .points
+        .iter()
+        .zip(labels)
+        .map(|(point, label)| InstanceData {
+            position: point.position,
+            remission: point.remission,
+            label,
+        });
+    let instances = match config.persistent.lod_enabled {
+        true => downsample_far_points(
+            instances,
+            config.persistent.lod_distance,
+            config.persistent.lod_voxel_size,
+        ),
+        false => instances.collect(),
+    };
     commands.spawn((
         mesh,
-        SpatialBundle::default(),
-        InstanceMaterialData(if frame.labels.is_some() {
-            frame
-                .points
-                .iter()
-                .zip(frame.labels.as_ref().unwrap().iter())
-                .map(|(point, label)| InstanceData {
-                    position: point.position,
-                    scale: 1.0,
-                    color: label_to_color(label, &config.actual_color_map, &config.default_color),
-                })
-                .collect()
-        } else {
-            frame
-                .points
-                .iter()
-                .map(|point| InstanceData {
-                    position: point.position,
-                    scale: 1.0,
-                    color: config.default_color,
-                })
-                .collect()
-        }),
+        SpatialBundle::from_transform(pose),
+        InstanceMaterialData(instances),
         NoFrustumCulling,
+        FrameSlot(slot),
+        RenderLayers::layer(sequence_layer(slot)),
     ));
 }
 
+/// Keeps only the first point of each voxel for points further away from the sensor than `distance`.
+fn downsample_far_points(
+    instances: impl Iterator<Item = InstanceData>,
+    distance: f32,
+    voxel_size: f32,
+) -> Vec<InstanceData> {
+    if voxel_size <= 0.0 {
+        return instances.collect();
+    }
+    let distance_squared = distance * distance;
+    let mut occupied_voxels = HashSet::new();
+    instances
+        .filter(|instance| {
+            if instance.position.length_squared() <= distance_squared {
+                return true;
+            }
+            let voxel = (instance.position / voxel_size).floor().as_ivec3();
+            occupied_voxels.insert(voxel)
+        })
+        .collect()
+}
+
+fn update_point_size(config: Res<PlayerConfig>, mut point_size: ResMut<PointSize>) {
+    if !config.is_changed() {
+        return;
+    }
+    let new_point_size = PointSize::from_config(&config);
+    if *point_size != new_point_size {
+        *point_size = new_point_size;
+    }
+}
+
+fn update_color_map(config: Res<PlayerConfig>, mut color_map: ResMut<PointColorMap>) {
+    if !config.is_changed() {
+        return;
+    }
+    let new_color_map = PointColorMap::from_config(&config);
+    if *color_map != new_color_map {
+        *color_map = new_color_map;
+    }
+}
+
 #[derive(Component)]
 struct ReadFrameTask {
     task: Task<Result<Frame, FrameReadError>>,
     frame_number: usize,
     sequence_number: u32,
+    slot: usize,
 }
 
 fn buffer_next_frames(mut commands: Commands, mut state: ResMut<PlayerState>) {
-    let thread_pool = IoTaskPool::get();
-    let mut buffer_frame = state.buffer_frame;
-    let max_buffer_frame = usize::min(
-        state.actual_frame + PlayerState::MAX_BUFFER_RANGE,
-        state.max_frame,
-    );
-    if buffer_frame == max_buffer_frame {
+    if state.is_memory_exhausted() {
         return;
     }
-    let sequence_number = state.sequence_number;
-    if let Some(sequence) = &mut state.sequence {
-        sequence
-            .load_states
-            .iter_mut()
-            .enumerate()
-            .skip(buffer_frame)
-            .skip_while(|(iter, load_state)| {
-                let skip = **load_state == LoadState::Loaded && *iter < max_buffer_frame;
-                if skip {
-                    buffer_frame = *iter;
-                }
-                skip
-            })
-            .take(PlayerState::BUFFER_SLIDING_WINDOW)
-            .for_each(|(iter, load_state)| {
-                if *load_state == LoadState::NotRequested {
-                    let points_path = sequence.point_folder.join(format!("{:0>6}.bin", iter));
-                    let labels_path = sequence
-                        .label_folder
-                        .as_ref()
-                        .map(|path| path.join(format!("{:0>6}.label", iter)));
-                    let task =
-                        thread_pool.spawn(async move { read_frame(points_path, labels_path) });
-                    commands.spawn(ReadFrameTask {
-                        task,
-                        frame_number: iter,
-                        sequence_number,
-                    });
-                    *load_state = LoadState::Requested;
-                }
-            });
-        state.buffer_frame = buffer_frame;
+    let thread_pool = IoTaskPool::get();
+    let window = state.playback_window();
+    for (slot_index, slot) in state.slots.iter_mut().enumerate() {
+        let sequence_number = slot.sequence_number;
+        if let Some(sequence) = &mut slot.sequence {
+            let frames: Vec<usize> = window
+                .iter()
+                .copied()
+                // failed frames are not read again, the buffer continues behind them
+                .skip_while(|frame| {
+                    matches!(
+                        sequence.load_states[*frame],
+                        LoadState::Loaded | LoadState::Failed
+                    )
+                })
+                .take(PlayerState::BUFFER_SLIDING_WINDOW)
+                .filter(|frame| sequence.load_states[*frame] == LoadState::NotRequested)
+                .collect();
+            for iter in frames {
+                let points_path = sequence.point_path(iter);
+                let labels_path = sequence.label_path(iter);
+                let task = thread_pool.spawn(async move { read_frame(points_path, labels_path) });
+                commands.spawn(ReadFrameTask {
+                    task,
+                    frame_number: iter,
+                    sequence_number,
+                    slot: slot_index,
+                });
+                sequence.load_states[iter] = LoadState::Requested;
+            }
+        }
     }
 }
 
+/// Unreadable frames per slot, reported together once no further reads failed for a while.
+#[derive(Default)]
+struct FailedFrames {
+    frames: [Vec<usize>; 2],
+    first_errors: [Option<String>; 2],
+    last_failure: f64,
+}
+
 fn handle_read_frames_task(
     mut commands: Commands,
     mut read_frame_tasks: Query<(Entity, &mut ReadFrameTask)>,
     mut state: ResMut<PlayerState>,
+    mut notifications: EventWriter<Notification>,
+    time: Res<Time>,
+    mut failed: Local<FailedFrames>,
 ) {
+    // a damaged part of a sequence fails frame by frame, one toast per frame would flood the screen
+    const REPORT_DELAY: f64 = 0.5;
     let frame_request = state.has_frame_request;
-    let sequence_number = state.sequence_number;
-    if let Some(sequence) = &mut state.sequence {
-        for (entity, mut task) in &mut read_frame_tasks {
-            if task.sequence_number != sequence_number {
+    let tick = state.tick;
+    for (entity, mut task) in &mut read_frame_tasks {
+        let slot = &mut state.slots[task.slot];
+        if slot.sequence.is_none() || task.sequence_number != slot.sequence_number {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        match future::block_on(future::poll_once(&mut task.task)) {
+            Some(Ok(frame)) => {
+                slot.insert_frame(task.frame_number, frame, tick);
                 commands.entity(entity).despawn();
-                continue;
             }
-            if let Some(Ok(frame)) = future::block_on(future::poll_once(&mut task.task)) {
-                sequence.frames[task.frame_number] = Some(frame);
-                sequence.load_states[task.frame_number] = LoadState::Loaded;
+            Some(Err(error)) => {
+                if let Some(sequence) = &mut slot.sequence {
+                    sequence.load_states[task.frame_number] = LoadState::Failed;
+                }
+                failed.frames[task.slot].push(task.frame_number);
+                failed.first_errors[task.slot].get_or_insert_with(|| error.to_string());
+                failed.last_failure = time.elapsed_seconds_f64();
                 commands.entity(entity).despawn();
-            } else if frame_request {
+            }
+            None if frame_request => {
                 commands.entity(entity).despawn();
-                sequence.load_states[task.frame_number] = LoadState::NotRequested;
+                if let Some(sequence) = &mut slot.sequence {
+                    sequence.load_states[task.frame_number] = LoadState::NotRequested;
+                }
             }
+            None => {}
         }
     }
+    state.evict_least_recently_used();
     if frame_request {
         state.has_frame_request = false;
-        state.buffer_frame = state.actual_frame;
     }
+    if time.elapsed_seconds_f64() - failed.last_failure < REPORT_DELAY {
+        return;
+    }
+    for slot in 0..failed.frames.len() {
+        if failed.frames[slot].is_empty() {
+            continue;
+        }
+        let mut frames = std::mem::take(&mut failed.frames[slot]);
+        let error = failed.first_errors[slot].take().unwrap_or_default();
+        let sequence_name = match slot {
+            COMPARISON_SLOT => " of the comparison",
+            _ => "",
+        };
+        let frames_text = match frames.as_slice() {
+            [frame] => format!("frame {frame}"),
+            _ => format!("frames {}", frame_ranges(&mut frames)),
+        };
+        notifications.send(Notification::error(format!(
+            "Cannot read {frames_text}{sequence_name}\n{error}"
+        )));
+    }
+}
+
+/// Consecutive frames joined to ranges, e.g. `120-180, 200`.
+fn frame_ranges(frames: &mut [usize]) -> String {
+    frames.sort_unstable();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &frame in frames.iter() {
+        match ranges.last_mut() {
+            Some((_, end)) if frame <= *end + 1 => *end = frame,
+            _ => ranges.push((frame, frame)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| match start == end {
+            true => start.to_string(),
+            false => format!("{start}-{end}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
-pub fn label_to_color(
-    label: &io::Label,
-    color_map: &HashMap<u16, [f32; 4]>,
-    default_color: &[f32; 4],
-) -> [f32; 4] {
-    *color_map.get(&label.label.into()).unwrap_or(default_color)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(playback_mode: PlaybackMode, max_frame: usize, start_frame: usize) -> PlayerState {
+        PlayerState {
+            playback_mode,
+            max_frame,
+            start_frame,
+            actual_frame: start_frame,
+            ..Default::default()
+        }
+    }
+
+    fn frames_after(player: &PlayerState, advanced: impl Iterator<Item = i64>) -> Vec<usize> {
+        advanced
+            .map(|advanced| player.frame_after(advanced))
+            .collect()
+    }
+
+    #[test]
+    fn once_stops_at_both_ends() {
+        let player = player(PlaybackMode::Once, 10, 5);
+        assert_eq!(player.frame_after(3), 8);
+        assert_eq!(player.frame_after(10), 10);
+        assert_eq!(player.frame_after(-10), 0);
+    }
+
+    #[test]
+    fn loop_wraps_in_both_directions() {
+        let player = player(PlaybackMode::Loop, 9, 8);
+        assert_eq!(frames_after(&player, 0..4), vec![8, 9, 0, 1]);
+        assert_eq!(frames_after(&player, (-10..=-8).rev()), vec![0, 9, 8]);
+    }
+
+    #[test]
+    fn repeat_range_loops_in_every_mode() {
+        for playback_mode in [PlaybackMode::Once, PlaybackMode::Loop] {
+            let mut player = player(playback_mode, 9, 3);
+            player.repeat_start = Some(2);
+            player.repeat_end = Some(4);
+            assert_eq!(frames_after(&player, 0..5), vec![3, 4, 2, 3, 4]);
+            assert_eq!(frames_after(&player, (-3..=0).rev()), vec![3, 2, 4, 3]);
+        }
+    }
+
+    #[test]
+    fn repeat_range_clamps_start_frame() {
+        let mut player = player(PlaybackMode::Loop, 9, 8);
+        player.repeat_start = Some(2);
+        player.repeat_end = Some(4);
+        assert_eq!(player.frame_after(0), 4);
+        player.start_frame = 0;
+        assert_eq!(player.frame_after(0), 2);
+    }
+
+    #[test]
+    fn ping_pong_reverses_at_the_ends() {
+        let player = player(PlaybackMode::PingPong, 3, 0);
+        assert_eq!(frames_after(&player, 0..8), vec![0, 1, 2, 3, 2, 1, 0, 1]);
+        assert_eq!(frames_after(&player, (-2..=0).rev()), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn ping_pong_continues_on_the_way_back() {
+        let mut player = player(PlaybackMode::PingPong, 3, 2);
+        player.start_on_return = true;
+        assert_eq!(frames_after(&player, 0..5), vec![2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn ping_pong_of_a_single_frame_stays() {
+        let mut player = player(PlaybackMode::PingPong, 9, 4);
+        player.repeat_start = Some(4);
+        player.repeat_end = Some(4);
+        assert_eq!(frames_after(&player, 0..3), vec![4, 4, 4]);
+    }
+
+    #[test]
+    fn consecutive_frames_are_joined_to_ranges() {
+        assert_eq!(
+            frame_ranges(&mut [182, 120, 121, 122, 200, 183]),
+            "120-122, 182-183, 200"
+        );
+        assert_eq!(frame_ranges(&mut [7, 7]), "7");
+    }
+
+    #[test]
+    fn playback_window_follows_repeat_range() {
+        let mut player = player(PlaybackMode::Once, 9, 3);
+        player.repeat_start = Some(2);
+        player.repeat_end = Some(4);
+        assert_eq!(player.playback_window(), vec![3, 4, 2]);
+        player.speed.time_scale = -1.0;
+        assert_eq!(player.playback_window(), vec![3, 2, 4]);
+    }
+
+    #[test]
+    fn playback_window_starts_outside_repeat_range() {
+        let mut player = player(PlaybackMode::Loop, 9, 8);
+        player.repeat_start = Some(2);
+        player.repeat_end = Some(3);
+        assert_eq!(player.playback_window(), vec![8, 2, 3]);
+    }
 }
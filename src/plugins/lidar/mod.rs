@@ -1,3 +1,4 @@
 mod lidar_plugin;
 mod instancing;
+mod eye_dome_lighting;
 pub use lidar_plugin::*;
\ No newline at end of file
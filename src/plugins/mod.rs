@@ -3,9 +3,21 @@ mod observer_plugin;
 mod fps_plugin;
 mod ui;
 mod config;
+mod reference_plugin;
+mod camera_path_plugin;
+mod annotations_plugin;
+mod heatmap_plugin;
+mod notification_plugin;
+mod key_bindings;
 
 pub use lidar::LidarPlugin;
-pub use observer_plugin::ObserverPlugin;
+pub use observer_plugin::{cursor_to_viewport, CameraController, CameraEvent, ObserverPlugin, Viewport};
 pub use fps_plugin::FpsWindowTitlePlugin;
 pub use ui::UiPlugin;
-pub use config::*;
\ No newline at end of file
+pub use reference_plugin::ReferencePlugin;
+pub use camera_path_plugin::{CameraPath, CameraPathPlugin};
+pub use annotations_plugin::{Annotation, AnnotationsPlugin, TimelineAnnotations};
+pub use heatmap_plugin::{HeatmapPlugin, LabelHeatmap};
+pub use notification_plugin::{Notification, NotificationHistory, NotificationLevel, NotificationPlugin};
+pub use config::*;
+pub use key_bindings::*;
\ No newline at end of file
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Align2, Color32, RichText},
+    EguiContexts,
+};
+
+/// Shows messages as toasts in the corner of the window, without blocking the application.
+/// All messages are kept in the [`NotificationHistory`] and written to the log.
+pub struct NotificationPlugin;
+
+impl Plugin for NotificationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Notification>()
+            .insert_resource(Toasts::default())
+            .insert_resource(NotificationHistory::default())
+            .add_system(collect_notifications)
+            .add_system(show_toasts.after(collect_notifications));
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotificationLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotificationLevel::Info => "Info",
+            NotificationLevel::Warning => "Warning",
+            NotificationLevel::Error => "Error",
+        }
+    }
+    pub fn color(&self) -> Color32 {
+        match self {
+            NotificationLevel::Info => Color32::from_rgb(100, 180, 255),
+            NotificationLevel::Warning => Color32::from_rgb(240, 190, 60),
+            NotificationLevel::Error => Color32::from_rgb(230, 90, 90),
+        }
+    }
+    fn toast_fill(&self) -> Color32 {
+        match self {
+            NotificationLevel::Info => Color32::from_rgb(20, 35, 60),
+            NotificationLevel::Warning => Color32::from_rgb(60, 45, 10),
+            NotificationLevel::Error => Color32::from_rgb(60, 20, 20),
+        }
+    }
+}
+
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub text: String,
+}
+
+impl Notification {
+    pub fn info(text: impl ToString) -> Self {
+        Self {
+            level: NotificationLevel::Info,
+            text: text.to_string(),
+        }
+    }
+    pub fn warning(text: impl ToString) -> Self {
+        Self {
+            level: NotificationLevel::Warning,
+            text: text.to_string(),
+        }
+    }
+    pub fn error(text: impl ToString) -> Self {
+        Self {
+            level: NotificationLevel::Error,
+            text: text.to_string(),
+        }
+    }
+}
+
+pub struct LoggedNotification {
+    pub level: NotificationLevel,
+    pub text: String,
+    /// Seconds since the start of the application.
+    pub time: f64,
+}
+
+/// Past notifications, the oldest are dropped beyond [`NotificationHistory::MAX_ENTRIES`].
+#[derive(Resource, Default)]
+pub struct NotificationHistory {
+    pub entries: Vec<LoggedNotification>,
+}
+
+impl NotificationHistory {
+    const MAX_ENTRIES: usize = 500;
+}
+
+struct Toast {
+    level: NotificationLevel,
+    text: String,
+    expires_at: f64,
+}
+
+#[derive(Resource, Default)]
+struct Toasts(Vec<Toast>);
+
+impl Toasts {
+    const DURATION_SECONDS: f64 = 6.0;
+    const MAX_VISIBLE: usize = 5;
+}
+
+fn collect_notifications(
+    time: Res<Time>,
+    mut notifications: EventReader<Notification>,
+    mut toasts: ResMut<Toasts>,
+    mut history: ResMut<NotificationHistory>,
+) {
+    let now = time.elapsed_seconds_f64();
+    toasts.0.retain(|toast| toast.expires_at > now);
+    for notification in notifications.iter() {
+        match notification.level {
+            NotificationLevel::Info => info!("{}", notification.text),
+            NotificationLevel::Warning => warn!("{}", notification.text),
+            NotificationLevel::Error => error!("{}", notification.text),
+        }
+        toasts.0.push(Toast {
+            level: notification.level,
+            text: notification.text.clone(),
+            expires_at: now + Toasts::DURATION_SECONDS,
+        });
+        history.entries.push(LoggedNotification {
+            level: notification.level,
+            text: notification.text.clone(),
+            time: now,
+        });
+    }
+    let overflow = history
+        .entries
+        .len()
+        .saturating_sub(NotificationHistory::MAX_ENTRIES);
+    if overflow > 0 {
+        history.entries.drain(..overflow);
+    }
+}
+
+fn show_toasts(mut egui_context: EguiContexts, mut toasts: ResMut<Toasts>) {
+    if toasts.0.is_empty() {
+        return;
+    }
+    let hidden = toasts.0.len().saturating_sub(Toasts::MAX_VISIBLE);
+    let mut dismissed = None;
+    egui::Area::new("Toasts")
+        .anchor(Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -70.0))
+        .show(egui_context.ctx_mut(), |ui| {
+            if hidden > 0 {
+                ui.label(RichText::new(format!("{hidden} more")).weak());
+            }
+            for (index, toast) in toasts.0.iter().enumerate().skip(hidden) {
+                let frame = egui::Frame::popup(ui.style()).fill(toast.level.toast_fill());
+                let response = frame
+                    .show(ui, |ui| {
+                        ui.set_max_width(400.0);
+                        ui.label(RichText::new(toast.level.name()).color(toast.level.color()));
+                        ui.label(&toast.text);
+                    })
+                    .response
+                    .interact(egui::Sense::click())
+                    .on_hover_text("Click to dismiss");
+                if response.clicked() {
+                    dismissed = Some(index);
+                }
+            }
+        });
+    if let Some(index) = dismissed {
+        toasts.0.remove(index);
+    }
+}
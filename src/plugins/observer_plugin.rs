@@ -1,40 +1,134 @@
-use crate::math::smooth_damp;
-use bevy::{input::mouse::MouseMotion, prelude::*, window::CursorGrabMode};
-use bevy_egui::EguiContexts;
+use crate::math::{lerp, smooth_damp};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    ecs::system::SystemParam,
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::{camera::Viewport as CameraViewport, view::RenderLayers},
+    window::{CursorGrabMode, PrimaryWindow},
+};
+use bevy_egui::{egui, EguiContexts};
 
-use super::PlayerConfig;
+use super::{
+    lidar::{sequence_layer, EgoPose, PlayerState},
+    Action, CameraBookmark, CameraFollow, CameraMode, PlayerConfig, ViewportLayout,
+};
 
 pub struct ObserverPlugin;
 
 impl Plugin for ObserverPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ClearColor(Color::rgb_u8(0, 21, 41)))
+            .add_event::<CameraEvent>()
             .add_startup_system(setup_camera)
-            .add_system(camera_control);
+            .add_system(update_viewports)
+            .add_system(update_active_viewport.after(update_viewports))
+            .add_system(update_controller_keys)
+            .add_system(gamepad_camera_control.before(camera_control))
+            .add_system(switch_camera_mode)
+            .add_system(camera_control)
+            .add_system(orbit_camera_control)
+            .add_system(focus_on_point)
+            .add_system(orthographic_camera_control)
+            .add_system(draw_scale_bar)
+            .add_system(
+                follow_ego
+                    .after(camera_control)
+                    .after(orbit_camera_control)
+                    .after(orthographic_camera_control),
+            )
+            .add_system(handle_camera_events)
+            .add_system(
+                animate_camera_transition
+                    .after(follow_ego)
+                    .after(switch_camera_mode),
+            )
+            .add_system(link_comparison_cameras.after(animate_camera_transition));
     }
 }
 
+const MIN_ORBIT_DISTANCE: f32 = 0.1;
+const MAX_ORBIT_DISTANCE: f32 = 1000.0;
+const ZOOM_FACTOR: f32 = 1.1;
+const ORBIT_PAN_SENSITIVITY: f32 = 0.002;
+const DOUBLE_CLICK_SECONDS: f64 = 0.3;
+const ORTHOGRAPHIC_DISTANCE: f32 = 500.0;
+const MIN_TOP_DOWN_SCALE: f32 = 0.001;
+const MAX_TOP_DOWN_SCALE: f32 = 10.0;
+const SCALE_BAR_TARGET_PIXELS: f32 = 120.0;
+const CHASE_SMOOTH_TIME: f32 = 0.3;
+const TRANSITION_SMOOTH_TIME: f32 = 0.25;
+const DEFAULT_TOP_DOWN_SCALE: f32 = 0.05;
+const GAMEPAD_LOOK_SPEED: f32 = 2.0;
+const GAMEPAD_ZOOM_SPEED: f32 = 2.0;
+const MAX_PITCH: f32 = 0.99 * std::f32::consts::FRAC_PI_2;
+pub const MAX_VIEWPORTS: usize = 4;
+
+fn default_camera_transform() -> Transform {
+    Transform::from_xyz(5.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y)
+}
+
+/// The orthographic modes look from far away at the center along the axes of the sensor.
+fn orthographic_camera_transform(mode: CameraMode, center: Vec3) -> Transform {
+    // sensor y and z are swapped in bevy
+    let (direction, up) = match mode {
+        CameraMode::Side => (Vec3::Z, Vec3::Y),
+        CameraMode::Front => (Vec3::X, Vec3::Y),
+        // the driving direction of the sensor points up on the screen
+        _ => (Vec3::Y, Vec3::X),
+    };
+    Transform::from_translation(center + direction * ORTHOGRAPHIC_DISTANCE).looking_at(center, up)
+}
+
+/// Moves an orthographic camera within its view plane so that it looks at the target.
+fn center_orthographic_on(transform: &Transform, target: Vec3) -> Transform {
+    let forward = transform.forward();
+    transform.with_translation(target + forward * forward.dot(transform.translation - target))
+}
+
 fn setup_camera(
     mut commands: Commands,
     config: Res<PlayerConfig>,
     mut clear_color: ResMut<ClearColor>,
 ) {
-    let transform = Transform::from_xyz(5.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y);
+    let transform = default_camera_transform();
     let (_, yaw, pitch) = transform.rotation.to_euler(EulerRot::ZYX);
-    commands
-        .spawn(Camera3dBundle {
-            transform,
-            projection: Projection::Perspective(PerspectiveProjection {
-                fov: config.persistent.camera_fov_degreas.to_radians(),
+    // cameras of viewports missing in the layout are deactivated
+    for index in 0..MAX_VIEWPORTS {
+        commands.spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    order: index as isize,
+                    is_active: index == 0,
+                    ..default()
+                },
+                camera_3d: Camera3d {
+                    // the main camera clears the whole window
+                    clear_color: match index {
+                        0 => ClearColorConfig::Default,
+                        _ => ClearColorConfig::None,
+                    },
+                    ..default()
+                },
+                transform,
+                projection: Projection::Perspective(PerspectiveProjection {
+                    fov: config.persistent.camera_fov_degreas.to_radians(),
+                    ..default()
+                }),
                 ..default()
-            }),
-            ..default()
-        })
-        .insert(CameraController {
-            pitch: DampedFloat::init(pitch),
-            yaw: DampedFloat::init(yaw),
-            ..default()
-        });
+            },
+            CameraController {
+                pitch: DampedFloat::init(pitch),
+                yaw: DampedFloat::init(yaw),
+                ..default()
+            },
+            Viewport {
+                index,
+                active: index == 0,
+            },
+            RenderLayers::layer(0).with(sequence_layer(0)),
+        ));
+    }
     let background_color = config.persistent.background_color;
     clear_color.0 = Color::rgb_u8(
         background_color[0],
@@ -43,6 +137,134 @@ fn setup_camera(
     );
 }
 
+/// Part of the window a camera renders to, the input goes to the active viewport.
+#[derive(Component)]
+pub struct Viewport {
+    pub index: usize,
+    pub active: bool,
+}
+
+impl Viewport {
+    pub fn is_main(&self) -> bool {
+        self.index == 0
+    }
+    /// With a comparison the right column of the layout shows the second sequence.
+    pub fn sequence_slot(&self, comparing: bool) -> usize {
+        match comparing {
+            true => self.index % 2,
+            false => 0,
+        }
+    }
+    /// Linked viewports of a comparison use the camera mode of the left viewport.
+    fn camera_mode_index(&self, comparing: bool) -> usize {
+        self.index - self.sequence_slot(comparing)
+    }
+}
+
+/// Position and size of a viewport in physical pixels, None if the layout has no such viewport.
+fn viewport_rect(
+    layout: ViewportLayout,
+    index: usize,
+    window_size: UVec2,
+) -> Option<(UVec2, UVec2)> {
+    let (columns, rows) = match layout {
+        ViewportLayout::Single => (1, 1),
+        ViewportLayout::Double => (2, 1),
+        ViewportLayout::Quad => (2, 2),
+    };
+    if index >= (columns * rows) as usize {
+        return None;
+    }
+    let size = (window_size / UVec2::new(columns, rows)).max(UVec2::ONE);
+    let cell = UVec2::new(index as u32 % columns, index as u32 / columns);
+    Some((cell * size, size))
+}
+
+/// Converts the cursor position of the window into the position expected by `Camera::viewport_to_world`,
+/// None if the cursor is outside of the viewport.
+pub fn cursor_to_viewport(camera: &Camera, window: &Window, cursor_position: Vec2) -> Option<Vec2> {
+    if !camera.is_active {
+        return None;
+    }
+    let (viewport_min, viewport_max) = camera.logical_viewport_rect()?;
+    // the cursor position starts at the bottom of the window, the viewport rect at the top
+    let position = Vec2::new(
+        cursor_position.x - viewport_min.x,
+        cursor_position.y - (window.height() - viewport_max.y),
+    );
+    let size = viewport_max - viewport_min;
+    (position.cmpge(Vec2::ZERO).all() && position.cmple(size).all()).then_some(position)
+}
+
+fn update_viewports(
+    config: Res<PlayerConfig>,
+    player: Res<PlayerState>,
+    query_window: Query<&Window, With<PrimaryWindow>>,
+    mut query: Query<(&mut Camera, &mut RenderLayers, &Viewport)>,
+) {
+    let Ok(window) = query_window.get_single() else {
+        return;
+    };
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    for (mut camera, mut render_layers, viewport) in query.iter_mut() {
+        let layers = RenderLayers::layer(0).with(sequence_layer(
+            viewport.sequence_slot(player.is_comparing()),
+        ));
+        if *render_layers != layers {
+            *render_layers = layers;
+        }
+        let rect = viewport_rect(
+            config.persistent.viewport_layout,
+            viewport.index,
+            window_size,
+        );
+        if camera.is_active != rect.is_some() {
+            camera.is_active = rect.is_some();
+        }
+        let old_rect = camera
+            .viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_position, viewport.physical_size));
+        if old_rect != rect {
+            camera.viewport = rect.map(|(physical_position, physical_size)| CameraViewport {
+                physical_position,
+                physical_size,
+                ..default()
+            });
+        }
+    }
+}
+
+/// Activates the viewport under the cursor, a drag stays in the viewport it started in.
+fn update_active_viewport(
+    btn: Res<Input<MouseButton>>,
+    query_window: Query<&Window, With<PrimaryWindow>>,
+    mut query: Query<(&Camera, &mut Viewport)>,
+) {
+    let Ok(window) = query_window.get_single() else {
+        return;
+    };
+    let hovered = window.cursor_position().and_then(|cursor_position| {
+        query
+            .iter()
+            .find(|(camera, _)| cursor_to_viewport(camera, window, cursor_position).is_some())
+            .map(|(_, viewport)| viewport.index)
+    });
+    let dragging = btn.get_pressed().next().is_some();
+    let active = match (hovered, dragging) {
+        (Some(index), false) => index,
+        _ => query
+            .iter()
+            .find(|(camera, viewport)| camera.is_active && viewport.active)
+            .map_or(0, |(_, viewport)| viewport.index),
+    };
+    for (_, mut viewport) in query.iter_mut() {
+        if viewport.active != (viewport.index == active) {
+            viewport.active = viewport.index == active;
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct CameraController {
     pub enabled: bool,
@@ -60,7 +282,18 @@ pub struct CameraController {
     yaw: DampedFloat,
     pub smooth_time: f32,
     pub velocity: Vec3,
+    /// Stick input of the fly mode, moved with the velocity like the keys.
+    gamepad_movement: Vec3,
+    gamepad_run: bool,
     pub old_cursor_position: Option<Vec2>,
+    pub mode: CameraMode,
+    pub pivot: Vec3,
+    distance: DampedFloat,
+    last_click_time: Option<f64>,
+    top_down_scale: DampedFloat,
+    perspective_translation: Vec3,
+    ego: DampedEgo,
+    transition: Option<CameraTransition>,
 }
 
 impl Default for CameraController {
@@ -89,11 +322,218 @@ impl Default for CameraController {
             },
             smooth_time: 0.01,
             velocity: Vec3::ZERO,
+            gamepad_movement: Vec3::ZERO,
+            gamepad_run: false,
             old_cursor_position: None,
+            mode: CameraMode::Fly,
+            pivot: Vec3::ZERO,
+            distance: DampedFloat::init(10.0),
+            last_click_time: None,
+            top_down_scale: DampedFloat::init(DEFAULT_TOP_DOWN_SCALE),
+            perspective_translation: Vec3::ZERO,
+            ego: DampedEgo::init(Vec3::ZERO, 0.0),
+            transition: None,
+        }
+    }
+}
+
+pub enum CameraEvent {
+    /// Stores the current view in the bookmark list, replaces the bookmark at the index or appends a new one.
+    SaveBookmark {
+        index: Option<usize>,
+        name: String,
+    },
+    RecallBookmark(usize),
+    /// Stores the current view in the slot of a number key, 0 is key 1.
+    SaveNumberedBookmark(usize),
+    RecallNumberedBookmark(usize),
+    /// Fits the points of the current frame into the view.
+    FrameAll,
+    Reset,
+    /// Cycles through the camera modes of the active viewport.
+    SwitchMode,
+}
+
+/// Moves the camera smoothly to a new pose, user input is ignored meanwhile.
+struct CameraTransition {
+    progress: DampedFloat,
+    start: Transform,
+    end: Transform,
+    start_fov: f32,
+    end_fov: f32,
+    start_scale: f32,
+    end_scale: f32,
+    pivot: Vec3,
+}
+
+/// Smoothed position and heading of the ego vehicle followed by the camera.
+struct DampedEgo {
+    translation: [DampedFloat; 3],
+    yaw: DampedFloat,
+}
+
+impl DampedEgo {
+    fn init(translation: Vec3, yaw: f32) -> Self {
+        Self {
+            translation: translation.to_array().map(DampedFloat::init),
+            yaw: DampedFloat::init(yaw),
+        }
+    }
+    fn damp_step(&mut self, translation: Vec3, yaw: f32, smooth_time: f32, delta_time: f32) {
+        for (damped, target) in self.translation.iter_mut().zip(translation.to_array()) {
+            damped.target = target;
+            damped.damp_step(smooth_time, delta_time);
+        }
+        // take the shortest way to avoid spinning around
+        self.yaw.target = self.yaw.actual + wrap_angle(yaw - self.yaw.actual);
+        self.yaw.damp_step(smooth_time, delta_time);
+    }
+    fn transform(&self) -> Transform {
+        let [x, y, z] = &self.translation;
+        Transform::from_xyz(x.actual, y.actual, z.actual)
+            .with_rotation(Quat::from_rotation_y(self.yaw.actual))
+    }
+}
+
+/// Maps an angle into the range [-PI, PI).
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI
+}
+
+impl CameraController {
+    /// Takes over the state of the leading camera of a comparison.
+    fn follow(&mut self, leader: &CameraController) {
+        self.mode = leader.mode;
+        self.pitch = leader.pitch;
+        self.yaw = leader.yaw;
+        self.pivot = leader.pivot;
+        self.distance = leader.distance;
+        self.top_down_scale = leader.top_down_scale;
+        self.perspective_translation = leader.perspective_translation;
+        self.velocity = leader.velocity;
+    }
+
+    /// Continues the control of the camera mode from a pose set from outside.
+    pub fn sync_to_transform(&mut self, transform: &Transform, mode: CameraMode) {
+        if mode.is_orthographic() {
+            return;
+        }
+        let (_, yaw, pitch) = transform.rotation.to_euler(EulerRot::ZYX);
+        self.yaw = DampedFloat::init(yaw);
+        self.pitch = DampedFloat::init(pitch.clamp(-MAX_PITCH, MAX_PITCH));
+        self.pivot = transform.translation + transform.forward() * self.distance.actual;
+    }
+
+    /// Moves the camera with the vehicle from the old to the new ego pose.
+    fn carry_along(
+        &mut self,
+        transform: &mut Transform,
+        old_ego: &Transform,
+        new_ego: &Transform,
+        yaw_change: f32,
+    ) {
+        let delta =
+            Transform::from_matrix(new_ego.compute_matrix() * old_ego.compute_matrix().inverse());
+        transform.translation = delta.transform_point(transform.translation);
+        transform.rotation = Quat::from_rotation_y(yaw_change) * transform.rotation;
+        self.pivot = delta.transform_point(self.pivot);
+        self.perspective_translation = delta.transform_point(self.perspective_translation);
+        self.yaw.actual += yaw_change;
+        self.yaw.target += yaw_change;
+    }
+
+    /// The current view of the camera.
+    fn bookmark(&self, name: String, transform: &Transform, fov_degrees: f32) -> CameraBookmark {
+        CameraBookmark {
+            name,
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            camera_mode: self.mode,
+            fov_degrees,
+            top_down_scale: self.top_down_scale.actual,
+            pivot: self.pivot.to_array(),
+        }
+    }
+
+    fn start_transition(
+        &mut self,
+        transform: &Transform,
+        projection: &Projection,
+        end: Transform,
+        end_fov: f32,
+        end_scale: f32,
+        pivot: Vec3,
+    ) {
+        let start_fov = match projection {
+            Projection::Perspective(perspective) => perspective.fov.to_degrees(),
+            Projection::Orthographic(_) => end_fov,
+        };
+        self.transition = Some(CameraTransition {
+            progress: DampedFloat {
+                actual: 0.0,
+                target: 1.0,
+                velocity: 0.0,
+            },
+            start: *transform,
+            end,
+            start_fov,
+            end_fov,
+            start_scale: self.top_down_scale.actual,
+            end_scale,
+            pivot,
+        });
+        self.enabled = false;
+    }
+
+    /// Turns the camera towards the pivot while keeping its position.
+    fn orbit_around(&mut self, pivot: Vec3, transform: &Transform) {
+        self.pivot = pivot;
+        let distance = transform.translation.distance(pivot);
+        if distance < MIN_ORBIT_DISTANCE {
+            self.distance = DampedFloat::init(MIN_ORBIT_DISTANCE);
+            return;
+        }
+        let rotation = transform.looking_at(pivot, Vec3::Y).rotation;
+        let (_, yaw, pitch) = rotation.to_euler(EulerRot::ZYX);
+        // take the shortest way to avoid spinning around
+        self.yaw = DampedFloat::init(self.yaw.actual + wrap_angle(yaw - self.yaw.actual));
+        self.pitch = DampedFloat::init(pitch.clamp(-MAX_PITCH, MAX_PITCH));
+        self.distance = DampedFloat::init(distance.min(MAX_ORBIT_DISTANCE));
+    }
+
+    fn look_update(
+        &mut self,
+        window: &mut Window,
+        btn: &Input<MouseButton>,
+        mouse_delta: Vec2,
+        delta_time: f32,
+    ) -> Quat {
+        if btn.just_pressed(MouseButton::Right) {
+            window.cursor.grab_mode = CursorGrabMode::Confined;
+            window.cursor.visible = false;
+            self.old_cursor_position = window.cursor_position();
         }
+
+        if mouse_delta != Vec2::ZERO && btn.pressed(MouseButton::Right) {
+            self.pitch.target = (self.pitch.target
+                - mouse_delta.y * 0.5 * self.sensitivity * delta_time)
+                .clamp(-MAX_PITCH, MAX_PITCH);
+            self.yaw.target -= mouse_delta.x * self.sensitivity * delta_time;
+        }
+        let smooth_time = self.smooth_time;
+        self.pitch.damp_step(smooth_time, delta_time);
+        self.yaw.damp_step(smooth_time, delta_time);
+
+        if btn.just_released(MouseButton::Right) {
+            window.cursor.grab_mode = CursorGrabMode::None;
+            window.cursor.visible = true;
+            window.set_cursor_position(self.old_cursor_position);
+        }
+        Quat::from_euler(EulerRot::ZYX, 0.0, self.yaw.actual, self.pitch.actual)
     }
 }
 
+#[derive(Clone, Copy)]
 struct DampedFloat {
     pub actual: f32,
     pub target: f32,
@@ -125,7 +565,7 @@ fn camera_control(
     mut mouse_events: EventReader<MouseMotion>,
     key_input: Res<Input<KeyCode>>,
     config: Res<PlayerConfig>,
-    mut query: Query<(&mut Transform, &mut CameraController), With<Camera>>,
+    mut query: Query<(&mut Transform, &mut CameraController, &Viewport), With<Camera>>,
     mut egui_ctx: EguiContexts,
 ) {
     if egui_ctx.ctx_mut().memory(|reader| reader.focus().is_some()) {
@@ -139,8 +579,8 @@ fn camera_control(
         mouse_delta += mouse_event.delta;
     }
 
-    for (mut transform, mut options) in query.iter_mut() {
-        if !options.enabled {
+    for (mut transform, mut options, viewport) in query.iter_mut() {
+        if !options.enabled || !viewport.active || options.mode != CameraMode::Fly {
             continue;
         }
         let mut window = query_window.single_mut();
@@ -165,15 +605,21 @@ fn camera_control(
             axis_input.y -= 1.0;
         }
 
+        // the keys move at full speed, the sticks with their deflection
+        let movement = match axis_input != Vec3::ZERO {
+            true => axis_input.normalize(),
+            false => options.gamepad_movement.clamp_length_max(1.0),
+        };
+
         // Apply movement update
-        if axis_input != Vec3::ZERO {
+        if movement != Vec3::ZERO {
             let max_speed = config.persistent.camera_speed
-                * if key_input.pressed(options.key_run) {
+                * if key_input.pressed(options.key_run) || options.gamepad_run {
                     options.run_modifier
                 } else {
                     1.0
                 };
-            options.velocity = axis_input.normalize() * max_speed;
+            options.velocity = movement * max_speed;
         } else {
             let friction = options.friction.clamp(0.0, 1.0);
             options.velocity *= 1.0 - friction;
@@ -188,31 +634,693 @@ fn camera_control(
             + options.velocity.z * delta_time * forward;
 
         // Apply look update
-        if btn.just_pressed(MouseButton::Right) {
-            window.cursor.grab_mode = CursorGrabMode::Confined;
-            window.cursor.visible = false;
-            options.old_cursor_position = window.cursor_position();
+        transform.rotation = options.look_update(&mut window, &btn, mouse_delta, delta_time);
+    }
+}
+
+fn update_controller_keys(config: Res<PlayerConfig>, mut query: Query<&mut CameraController>) {
+    if !config.is_changed() {
+        return;
+    }
+    let bindings = &config.persistent.key_bindings;
+    for mut options in query.iter_mut() {
+        options.key_forward = bindings.get(Action::MoveForward).key;
+        options.key_back = bindings.get(Action::MoveBack).key;
+        options.key_left = bindings.get(Action::MoveLeft).key;
+        options.key_right = bindings.get(Action::MoveRight).key;
+        options.key_up = bindings.get(Action::MoveUp).key;
+        options.key_down = bindings.get(Action::MoveDown).key;
+        options.key_run = bindings.get(Action::Run).key;
+    }
+}
+
+fn switch_camera_mode(
+    config: Res<PlayerConfig>,
+    player: Res<PlayerState>,
+    mut query: Query<(
+        &mut Transform,
+        &mut Projection,
+        &mut CameraController,
+        &Viewport,
+    )>,
+) {
+    for (mut transform, mut projection, mut options, viewport) in query.iter_mut() {
+        let mode = config
+            .persistent
+            .camera_mode_of(viewport.camera_mode_index(player.is_comparing()));
+        if options.mode == mode {
+            continue;
         }
+        if options.mode.is_orthographic() {
+            *projection = Projection::Perspective(PerspectiveProjection {
+                fov: config.persistent.camera_fov_degreas.to_radians(),
+                ..default()
+            });
+            transform.translation = options.perspective_translation;
+            transform.rotation =
+                Quat::from_euler(EulerRot::ZYX, 0.0, options.yaw.actual, options.pitch.actual);
+        }
+        match mode {
+            CameraMode::Fly => {}
+            CameraMode::Orbit => {
+                let pivot = options.pivot;
+                options.orbit_around(pivot, &transform);
+            }
+            CameraMode::TopDown | CameraMode::Side | CameraMode::Front => {
+                options.perspective_translation = transform.translation;
+                *transform = orthographic_camera_transform(mode, transform.translation);
+                *projection = Projection::Orthographic(OrthographicProjection {
+                    scale: options.top_down_scale.actual,
+                    far: 2.0 * ORTHOGRAPHIC_DISTANCE,
+                    ..default()
+                });
+            }
+        }
+        options.velocity = Vec3::ZERO;
+        options.mode = mode;
+    }
+}
 
-        if mouse_delta != Vec2::ZERO && btn.pressed(MouseButton::Right) {
-            options.pitch.target = (options.pitch.target
-                - mouse_delta.y * 0.5 * options.sensitivity * delta_time)
-                .clamp(
-                    -0.99 * std::f32::consts::FRAC_PI_2,
-                    0.99 * std::f32::consts::FRAC_PI_2,
-                );
-            options.yaw.target -= mouse_delta.x * options.sensitivity * delta_time;
+fn scroll_amount(wheel_events: &mut EventReader<MouseWheel>) -> f32 {
+    wheel_events
+        .iter()
+        .map(|wheel_event| match wheel_event.unit {
+            MouseScrollUnit::Line => wheel_event.y,
+            MouseScrollUnit::Pixel => wheel_event.y / 100.0,
+        })
+        .sum()
+}
+
+fn orbit_camera_control(
+    time: Res<Time>,
+    mut query_window: Query<&mut Window>,
+    btn: Res<Input<MouseButton>>,
+    mut mouse_events: EventReader<MouseMotion>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut query: Query<(&mut Transform, &mut CameraController, &Viewport), With<Camera>>,
+    mut egui_ctx: EguiContexts,
+) {
+    let ctx = egui_ctx.ctx_mut();
+    if ctx.memory(|reader| reader.focus().is_some()) {
+        return;
+    }
+    let pointer_over_ui = ctx.is_pointer_over_area();
+    let delta_time = time.delta_seconds();
+
+    let mut mouse_delta = Vec2::ZERO;
+    for mouse_event in mouse_events.iter() {
+        mouse_delta += mouse_event.delta;
+    }
+    let scroll = scroll_amount(&mut wheel_events);
+
+    for (mut transform, mut options, viewport) in query.iter_mut() {
+        if !options.enabled || !viewport.active || options.mode != CameraMode::Orbit {
+            continue;
+        }
+        let mut window = query_window.single_mut();
+        let rotation = options.look_update(&mut window, &btn, mouse_delta, delta_time);
+
+        // Apply pan update, the pivot follows the mouse in the view plane
+        if mouse_delta != Vec2::ZERO && btn.pressed(MouseButton::Middle) {
+            let pan_speed = options.distance.actual * ORBIT_PAN_SENSITIVITY;
+            let pan =
+                (transform.up() * mouse_delta.y - transform.right() * mouse_delta.x) * pan_speed;
+            options.pivot += pan;
+        }
+
+        // Apply zoom update
+        if scroll != 0.0 && !pointer_over_ui {
+            options.distance.target = (options.distance.target * ZOOM_FACTOR.powf(-scroll))
+                .clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
         }
         let smooth_time = options.smooth_time;
-        options.pitch.damp_step(smooth_time, delta_time);
-        options.yaw.damp_step(smooth_time, delta_time);
-        transform.rotation =
-            Quat::from_euler(EulerRot::ZYX, 0.0, options.yaw.actual, options.pitch.actual);
+        options.distance.damp_step(smooth_time, delta_time);
 
-        if btn.just_released(MouseButton::Right) {
-            window.cursor.grab_mode = CursorGrabMode::None;
-            window.cursor.visible = true;
-            window.set_cursor_position(options.old_cursor_position);
+        transform.rotation = rotation;
+        transform.translation = options.pivot + rotation * Vec3::Z * options.distance.actual;
+    }
+}
+
+/// A double click on a point moves the camera target to this point.
+fn focus_on_point(
+    time: Res<Time>,
+    btn: Res<Input<MouseButton>>,
+    query_window: Query<&Window>,
+    player: Res<PlayerState>,
+    config: Res<PlayerConfig>,
+    mut query: Query<(
+        &Camera,
+        &GlobalTransform,
+        &Transform,
+        &Projection,
+        &mut CameraController,
+        &Viewport,
+    )>,
+    mut egui_ctx: EguiContexts,
+) {
+    if !btn.just_pressed(MouseButton::Left) || egui_ctx.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let Ok(window) = query_window.get_single() else {
+        return;
+    };
+    let Some(cursor_position) = window.cursor_position() else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+    for (camera, camera_transform, transform, projection, mut options, viewport) in query.iter_mut()
+    {
+        if !viewport.active {
+            continue;
+        }
+        let is_double_click = matches!(
+            options.last_click_time,
+            Some(last_click_time) if now - last_click_time < DOUBLE_CLICK_SECONDS
+        );
+        options.last_click_time = Some(now);
+        if !is_double_click || !options.enabled {
+            continue;
+        }
+        options.last_click_time = None;
+        let slot = viewport.sequence_slot(player.is_comparing());
+        let Some(frame) = player.get_frame_content_of(slot) else {
+            continue;
+        };
+        let pose = EgoPose(player.get_pose_of(slot));
+        let Some(mouse_position) = cursor_to_viewport(camera, window, cursor_position) else {
+            continue;
+        };
+        let Some(ray) = camera.viewport_to_world(camera_transform, mouse_position) else {
+            continue;
+        };
+        let Some(index) = frame.pick_point(pose.ray_to_sensor(ray), config.persistent.point_size)
+        else {
+            continue;
+        };
+        let target = pose.0.transform_point(frame.points[index].position);
+        let end = match options.mode.is_orthographic() {
+            true => center_orthographic_on(transform, target),
+            false => transform.looking_at(target, Vec3::Y),
+        };
+        let end_scale = options.top_down_scale.actual;
+        options.start_transition(
+            transform,
+            projection,
+            end,
+            config.persistent.camera_fov_degreas,
+            end_scale,
+            target,
+        );
+    }
+}
+
+fn orthographic_camera_control(
+    time: Res<Time>,
+    btn: Res<Input<MouseButton>>,
+    mut mouse_events: EventReader<MouseMotion>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut query: Query<(
+        &mut Transform,
+        &mut Projection,
+        &mut CameraController,
+        &Viewport,
+    )>,
+    mut egui_ctx: EguiContexts,
+) {
+    let ctx = egui_ctx.ctx_mut();
+    if ctx.memory(|reader| reader.focus().is_some()) {
+        return;
+    }
+    let pointer_over_ui = ctx.is_pointer_over_area();
+    let delta_time = time.delta_seconds();
+
+    let mut mouse_delta = Vec2::ZERO;
+    for mouse_event in mouse_events.iter() {
+        mouse_delta += mouse_event.delta;
+    }
+    let scroll = scroll_amount(&mut wheel_events);
+
+    for (mut transform, mut projection, mut options, viewport) in query.iter_mut() {
+        if !options.enabled || !viewport.active || !options.mode.is_orthographic() {
+            continue;
+        }
+        let Projection::Orthographic(orthographic) = projection.as_mut() else {
+            continue;
+        };
+
+        // Apply pan update, one pixel of mouse movement moves the ground by one pixel
+        if mouse_delta != Vec2::ZERO && btn.any_pressed([MouseButton::Right, MouseButton::Middle]) {
+            let pan = (transform.up() * mouse_delta.y - transform.right() * mouse_delta.x)
+                * orthographic.scale;
+            transform.translation += pan;
+        }
+
+        // Apply zoom update
+        if scroll != 0.0 && !pointer_over_ui {
+            options.top_down_scale.target = (options.top_down_scale.target
+                * ZOOM_FACTOR.powf(-scroll))
+            .clamp(MIN_TOP_DOWN_SCALE, MAX_TOP_DOWN_SCALE);
+        }
+        let smooth_time = options.smooth_time;
+        options.top_down_scale.damp_step(smooth_time, delta_time);
+        if orthographic.scale != options.top_down_scale.actual {
+            orthographic.scale = options.top_down_scale.actual;
+        }
+    }
+}
+
+/// Draws a scale bar with a round length into the lower left corner of the viewport.
+fn draw_scale_bar(mut egui_context: EguiContexts, query: Query<(&Camera, &Projection)>) {
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    for (camera, projection) in &query {
+        let Projection::Orthographic(orthographic) = projection else {
+            continue;
+        };
+        if !camera.is_active {
+            continue;
+        }
+        let Some((viewport_min, viewport_max)) = camera.logical_viewport_rect() else {
+            continue;
+        };
+        let meters_per_pixel = orthographic.scale;
+        let target_meters = SCALE_BAR_TARGET_PIXELS * meters_per_pixel;
+        let magnitude = 10f32.powf(target_meters.log10().floor());
+        let meters = [5.0, 2.0, 1.0]
+            .into_iter()
+            .map(|factor| factor * magnitude)
+            .find(|meters| *meters <= target_meters)
+            .unwrap_or(magnitude);
+        let length = meters / meters_per_pixel;
+
+        let start = egui::pos2(viewport_min.x + 20.0, viewport_max.y - 60.0);
+        let end = start + egui::vec2(length, 0.0);
+        let stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
+        painter.line_segment([start, end], stroke);
+        for x in [start.x, end.x] {
+            painter.line_segment(
+                [egui::pos2(x, start.y - 5.0), egui::pos2(x, start.y + 5.0)],
+                stroke,
+            );
+        }
+        let text = match meters >= 1.0 {
+            true => format!("{} m", meters.round()),
+            false => format!("{} cm", (meters * 100.0).round()),
+        };
+        painter.text(
+            start + egui::vec2(length / 2.0, -6.0),
+            egui::Align2::CENTER_BOTTOM,
+            text,
+            egui::FontId::proportional(14.0),
+            egui::Color32::WHITE,
+        );
+    }
+}
+
+/// Moves the camera along with the ego vehicle, keeping its offset relative to the sensor.
+/// Each viewport follows the vehicle of the sequence it shows.
+fn follow_ego(
+    time: Res<Time>,
+    config: Res<PlayerConfig>,
+    player: Res<PlayerState>,
+    mut query: Query<(&mut Transform, &mut CameraController, &Viewport), With<Camera>>,
+) {
+    let delta_time = time.delta_seconds();
+    let comparing = player.is_comparing();
+    for (mut transform, mut options, viewport) in query.iter_mut() {
+        if !options.enabled {
+            continue;
+        }
+        let ego_pose = player.get_pose_of(viewport.sequence_slot(comparing));
+        let target_translation = ego_pose.translation;
+        // the sensor looks along its x axis
+        let forward = ego_pose.rotation * Vec3::X;
+        let target_yaw = (-forward.z).atan2(forward.x);
+        let old_ego = options.ego.transform();
+        let old_yaw = options.ego.yaw.actual;
+        match config.persistent.camera_follow {
+            CameraFollow::Off | CameraFollow::Sensor => {
+                options.ego = DampedEgo::init(target_translation, target_yaw)
+            }
+            CameraFollow::Chase => options.ego.damp_step(
+                target_translation,
+                target_yaw,
+                CHASE_SMOOTH_TIME,
+                delta_time,
+            ),
+        }
+        if config.persistent.camera_follow == CameraFollow::Off {
+            continue;
+        }
+        let yaw_change = options.ego.yaw.actual - old_yaw;
+        let new_ego = options.ego.transform();
+        options.carry_along(&mut transform, &old_ego, &new_ego, yaw_change);
+    }
+}
+
+fn handle_camera_events(
+    mut events: EventReader<CameraEvent>,
+    mut config: ResMut<PlayerConfig>,
+    player: Res<PlayerState>,
+    mut query: Query<(
+        &Camera,
+        &Transform,
+        &Projection,
+        &mut CameraController,
+        &Viewport,
+    )>,
+) {
+    for event in events.iter() {
+        let Some((camera, transform, projection, mut options, viewport)) =
+            query.iter_mut().find(|(.., viewport)| viewport.active)
+        else {
+            return;
+        };
+        let fov_degrees = config.persistent.camera_fov_degreas;
+        let orthographic = options.mode.is_orthographic();
+        let comparing = player.is_comparing();
+        match event {
+            CameraEvent::SaveBookmark { index, name } => {
+                let bookmark = options.bookmark(name.clone(), transform, fov_degrees);
+                let bookmarks = &mut config.persistent.camera_bookmarks;
+                match index.and_then(|index| bookmarks.get_mut(index)) {
+                    Some(existing) => *existing = bookmark,
+                    None => bookmarks.push(bookmark),
+                }
+                config.save();
+            }
+            CameraEvent::SaveNumberedBookmark(slot) => {
+                let bookmark =
+                    options.bookmark(format!("View {}", slot + 1), transform, fov_degrees);
+                let Some(numbered) = config.persistent.numbered_bookmarks.get_mut(*slot) else {
+                    continue;
+                };
+                *numbered = Some(bookmark);
+                config.save();
+            }
+            CameraEvent::RecallBookmark(_) | CameraEvent::RecallNumberedBookmark(_) => {
+                let bookmark = match *event {
+                    CameraEvent::RecallNumberedBookmark(slot) => config
+                        .persistent
+                        .numbered_bookmarks
+                        .get(slot)
+                        .cloned()
+                        .flatten(),
+                    CameraEvent::RecallBookmark(index) => {
+                        config.persistent.camera_bookmarks.get(index).cloned()
+                    }
+                    _ => None,
+                };
+                let Some(bookmark) = bookmark else {
+                    continue;
+                };
+                options.start_transition(
+                    transform,
+                    projection,
+                    Transform::from_translation(Vec3::from_array(bookmark.translation))
+                        .with_rotation(Quat::from_array(bookmark.rotation).normalize()),
+                    bookmark.fov_degrees,
+                    bookmark.top_down_scale,
+                    Vec3::from_array(bookmark.pivot),
+                );
+                config
+                    .persistent
+                    .set_camera_mode(viewport.camera_mode_index(comparing), bookmark.camera_mode);
+                config.persistent.camera_fov_degreas = bookmark.fov_degrees;
+                config.save();
+            }
+            CameraEvent::FrameAll => {
+                let slot = viewport.sequence_slot(comparing);
+                let Some(frame) = player.get_frame_content_of(slot) else {
+                    continue;
+                };
+                let pose = player.get_pose_of(slot);
+                if frame.points.is_empty() {
+                    continue;
+                }
+                let (min, max) = frame.points.iter().fold(
+                    (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+                    |(min, max), point| {
+                        let position = pose.transform_point(point.position);
+                        (min.min(position), max.max(position))
+                    },
+                );
+                let center = (min + max) / 2.0;
+                let half_size = (max - min) / 2.0;
+                let radius = half_size.length().max(MIN_ORBIT_DISTANCE);
+                let (end, end_scale) = match orthographic {
+                    true => {
+                        // only the extent within the view plane matters
+                        let forward = transform.forward();
+                        let radius = (half_size - forward * half_size.dot(forward)).length();
+                        let viewport_size = camera.logical_viewport_size().unwrap_or(Vec2::ONE);
+                        let scale = 2.0 * radius / viewport_size.min_element();
+                        (
+                            center_orthographic_on(transform, center),
+                            scale.clamp(MIN_TOP_DOWN_SCALE, MAX_TOP_DOWN_SCALE),
+                        )
+                    }
+                    false => {
+                        // the bounding sphere touches the border of the view
+                        let distance = (radius / (fov_degrees.to_radians() / 2.0).sin())
+                            .min(MAX_ORBIT_DISTANCE);
+                        let end =
+                            transform.with_translation(center - transform.forward() * distance);
+                        (end, options.top_down_scale.actual)
+                    }
+                };
+                options.start_transition(
+                    transform,
+                    projection,
+                    end,
+                    fov_degrees,
+                    end_scale,
+                    center,
+                );
+            }
+            CameraEvent::Reset => {
+                let end = match orthographic {
+                    true => orthographic_camera_transform(options.mode, Vec3::ZERO),
+                    false => default_camera_transform(),
+                };
+                options.start_transition(
+                    transform,
+                    projection,
+                    end,
+                    fov_degrees,
+                    DEFAULT_TOP_DOWN_SCALE,
+                    Vec3::ZERO,
+                );
+            }
+            CameraEvent::SwitchMode => {
+                let index = viewport.camera_mode_index(comparing);
+                let mode = config.persistent.camera_mode_of(index).next();
+                config.persistent.set_camera_mode(index, mode);
+                config.save();
+            }
+        }
+    }
+}
+
+fn animate_camera_transition(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut Projection, &mut CameraController), With<Camera>>,
+) {
+    let delta_time = time.delta_seconds();
+    for (mut transform, mut projection, mut options) in query.iter_mut() {
+        let Some(transition) = &mut options.transition else {
+            continue;
+        };
+        transition
+            .progress
+            .damp_step(TRANSITION_SMOOTH_TIME, delta_time);
+        let alpha = transition.progress.actual;
+        transform.translation = transition
+            .start
+            .translation
+            .lerp(transition.end.translation, alpha);
+        transform.rotation = transition
+            .start
+            .rotation
+            .slerp(transition.end.rotation, alpha);
+        match projection.as_mut() {
+            Projection::Perspective(perspective) => {
+                perspective.fov = lerp(alpha, transition.start_fov, transition.end_fov).to_radians()
+            }
+            // zoom with a constant speed
+            Projection::Orthographic(orthographic) => {
+                orthographic.scale = transition.start_scale
+                    * (transition.end_scale / transition.start_scale).powf(alpha)
+            }
+        }
+        if alpha < 0.999 {
+            continue;
+        }
+
+        // hand the pose over to the controller of the camera mode
+        let end = transition.end;
+        let end_scale = transition.end_scale;
+        let pivot = transition.pivot;
+        *transform = end;
+        options.transition = None;
+        options.enabled = true;
+        match options.mode {
+            CameraMode::Fly => options.sync_to_transform(&end, CameraMode::Fly),
+            CameraMode::Orbit => options.orbit_around(pivot, &end),
+            CameraMode::TopDown | CameraMode::Side | CameraMode::Front => {
+                options.top_down_scale = DampedFloat::init(end_scale)
+            }
+        }
+        options.pivot = pivot;
+    }
+}
+
+/// The viewports of a comparison show the same view, the viewport with the input leads.
+/// While following the vehicles, the view is the same relative to the vehicle of each sequence.
+fn link_comparison_cameras(
+    player: Res<PlayerState>,
+    config: Res<PlayerConfig>,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Projection,
+        &mut CameraController,
+        &Viewport,
+    )>,
+) {
+    if !player.is_comparing() {
+        return;
+    }
+    let mut viewports = [None; MAX_VIEWPORTS];
+    for (entity, .., viewport) in query.iter() {
+        viewports[viewport.index] = Some((entity, viewport.active));
+    }
+    for pair in viewports.chunks_exact(2) {
+        let [Some((left, _)), Some((right, right_active))] = [pair[0], pair[1]] else {
+            continue;
+        };
+        let (leader, follower) = match right_active {
+            true => (right, left),
+            false => (left, right),
+        };
+        let Ok([leader, mut follower]) = query.get_many_mut([leader, follower]) else {
+            continue;
+        };
+        *follower.1 = *leader.1;
+        *follower.2 = leader.2.clone();
+        follower.3.follow(&leader.3);
+        if config.persistent.camera_follow != CameraFollow::Off {
+            let yaw_change = follower.3.ego.yaw.actual - leader.3.ego.yaw.actual;
+            let follower_ego = follower.3.ego.transform();
+            let leader_ego = leader.3.ego.transform();
+            follower
+                .3
+                .carry_along(&mut follower.1, &leader_ego, &follower_ego, yaw_change);
+        }
+    }
+}
+
+/// Analog input of all connected gamepads.
+#[derive(SystemParam)]
+struct GamepadInput<'w> {
+    gamepads: Res<'w, Gamepads>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    button_axes: Res<'w, Axis<GamepadButton>>,
+}
+
+impl GamepadInput<'_> {
+    fn axis(&self, axis_type: GamepadAxisType) -> f32 {
+        self.gamepads
+            .iter()
+            .filter_map(|gamepad| self.axes.get(GamepadAxis::new(gamepad, axis_type)))
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+    fn button(&self, button_type: GamepadButtonType) -> f32 {
+        self.gamepads
+            .iter()
+            .filter_map(|gamepad| {
+                self.button_axes
+                    .get(GamepadButton::new(gamepad, button_type))
+            })
+            .sum::<f32>()
+            .clamp(0.0, 1.0)
+    }
+    fn left_stick(&self) -> Vec2 {
+        Vec2::new(
+            self.axis(GamepadAxisType::LeftStickX),
+            self.axis(GamepadAxisType::LeftStickY),
+        )
+    }
+    fn right_stick(&self) -> Vec2 {
+        Vec2::new(
+            self.axis(GamepadAxisType::RightStickX),
+            self.axis(GamepadAxisType::RightStickY),
+        )
+    }
+}
+
+/// Left stick moves, right stick looks and the triggers move up and down.
+fn gamepad_camera_control(
+    time: Res<Time>,
+    config: Res<PlayerConfig>,
+    gamepad: GamepadInput,
+    mut query: Query<(&mut Transform, &mut CameraController, &Viewport), With<Camera>>,
+) {
+    if gamepad.gamepads.iter().next().is_none() {
+        // a disconnected gamepad must not keep the camera moving
+        for (_, mut options, _) in &mut query {
+            if options.gamepad_movement != Vec3::ZERO {
+                options.gamepad_movement = Vec3::ZERO;
+            }
+        }
+        return;
+    }
+    let delta_time = time.delta_seconds();
+    let left_stick = gamepad.left_stick();
+    let right_stick = gamepad.right_stick();
+    let vertical = gamepad.button(GamepadButtonType::RightTrigger2)
+        - gamepad.button(GamepadButtonType::LeftTrigger2);
+    let run = gamepad.button(GamepadButtonType::LeftThumb) > 0.5;
+
+    for (mut transform, mut options, viewport) in query.iter_mut() {
+        if !options.enabled || !viewport.active {
+            continue;
+        }
+        match options.mode {
+            CameraMode::Fly | CameraMode::Orbit => {
+                if right_stick != Vec2::ZERO {
+                    options.pitch.target = (options.pitch.target
+                        + right_stick.y * GAMEPAD_LOOK_SPEED * delta_time)
+                        .clamp(-MAX_PITCH, MAX_PITCH);
+                    options.yaw.target -= right_stick.x * GAMEPAD_LOOK_SPEED * delta_time;
+                }
+            }
+            CameraMode::TopDown | CameraMode::Side | CameraMode::Front => {}
+        }
+        match options.mode {
+            CameraMode::Fly => {
+                // applied by the camera control with the friction and run modifier of the keys
+                options.gamepad_movement = Vec3::new(left_stick.x, vertical, left_stick.y);
+                options.gamepad_run = run;
+            }
+            CameraMode::Orbit => {
+                options.distance.target = (options.distance.target
+                    * (1.0 - left_stick.y * GAMEPAD_ZOOM_SPEED * delta_time))
+                    .clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+            }
+            CameraMode::TopDown | CameraMode::Side | CameraMode::Front => {
+                let meters_per_second =
+                    options.top_down_scale.actual * config.persistent.camera_speed * 50.0;
+                let pan = (transform.up() * left_stick.y + transform.right() * left_stick.x)
+                    * meters_per_second
+                    * delta_time;
+                transform.translation += pan;
+                options.top_down_scale.target = (options.top_down_scale.target
+                    * (1.0 - right_stick.y * GAMEPAD_ZOOM_SPEED * delta_time))
+                    .clamp(MIN_TOP_DOWN_SCALE, MAX_TOP_DOWN_SCALE);
+            }
         }
     }
 }
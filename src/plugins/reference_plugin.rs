@@ -0,0 +1,306 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use bevy_egui::{egui, EguiContexts};
+
+use super::{lidar::EgoPose, PlayerConfig};
+
+/// Toggleable helpers to judge distances: a ground grid, the sensor axes and range rings.
+pub struct ReferencePlugin;
+
+impl Plugin for ReferencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_reference_frame)
+            .add_system(update_reference_meshes)
+            .add_system(follow_ego_pose)
+            .add_system(draw_range_ring_labels);
+    }
+}
+
+/// Root of all helpers, placed at the sensor pose.
+#[derive(Component)]
+pub struct ReferenceFrame;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum ReferenceHelper {
+    Grid,
+    Axes,
+    RangeRings,
+}
+
+/// Parameters of the helper meshes, unrelated config changes keep the built mesh.
+#[derive(Clone, Copy, PartialEq)]
+enum HelperShape {
+    Grid {
+        spacing: f32,
+        extent: f32,
+        height: f32,
+    },
+    Axes,
+    RangeRings {
+        spacing: f32,
+        count: u32,
+        height: f32,
+    },
+}
+
+impl HelperShape {
+    fn lines(self) -> Vec<ColoredLine> {
+        match self {
+            HelperShape::Grid {
+                spacing,
+                extent,
+                height,
+            } => grid_lines(spacing, extent, height),
+            HelperShape::Axes => axes_lines(),
+            HelperShape::RangeRings {
+                spacing,
+                count,
+                height,
+            } => range_ring_lines(spacing, count, height),
+        }
+    }
+}
+
+/// Shape the mesh of a helper was built from.
+#[derive(Component, Default)]
+struct BuiltShape {
+    shape: Option<HelperShape>,
+    empty: bool,
+}
+
+const AXES_LENGTH: f32 = 2.0;
+const RANGE_RING_SEGMENTS: usize = 128;
+
+fn setup_reference_frame(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    let material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        unlit: true,
+        ..default()
+    });
+    commands
+        .spawn((ReferenceFrame, SpatialBundle::default()))
+        .with_children(|parent| {
+            for helper in [
+                ReferenceHelper::Grid,
+                ReferenceHelper::Axes,
+                ReferenceHelper::RangeRings,
+            ] {
+                parent.spawn((
+                    helper,
+                    BuiltShape::default(),
+                    PbrBundle {
+                        material: material.clone(),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+fn update_reference_meshes(
+    config: Res<PlayerConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut helpers: Query<(
+        &ReferenceHelper,
+        &mut BuiltShape,
+        &mut Handle<Mesh>,
+        &mut Visibility,
+    )>,
+    added_helpers: Query<(), Added<ReferenceHelper>>,
+) {
+    if !config.is_changed() && added_helpers.is_empty() {
+        return;
+    }
+    let config = &config.persistent;
+    for (helper, mut built, mut mesh, mut visibility) in &mut helpers {
+        let (visible, new_shape) = match helper {
+            ReferenceHelper::Grid => (
+                config.grid_visible,
+                HelperShape::Grid {
+                    spacing: config.grid_spacing,
+                    extent: config.grid_extent,
+                    height: config.grid_height,
+                },
+            ),
+            ReferenceHelper::Axes => (config.axes_visible, HelperShape::Axes),
+            ReferenceHelper::RangeRings => (
+                config.range_rings_visible,
+                HelperShape::RangeRings {
+                    spacing: config.range_ring_spacing,
+                    count: config.range_ring_count,
+                    height: config.grid_height,
+                },
+            ),
+        };
+        if visible && built.shape != Some(new_shape) {
+            let lines = new_shape.lines();
+            built.shape = Some(new_shape);
+            built.empty = lines.is_empty();
+            let new_mesh = line_mesh(lines);
+            match meshes.get_mut(&mesh) {
+                Some(old_mesh) => *old_mesh = new_mesh,
+                None => *mesh = meshes.add(new_mesh),
+            }
+        }
+        let new_visibility = match visible && !built.empty {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        // only compared to not mark the visibility as changed on every config change
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+}
+
+fn follow_ego_pose(
+    ego_pose: Res<EgoPose>,
+    mut reference_frame: Query<&mut Transform, With<ReferenceFrame>>,
+) {
+    if !ego_pose.is_changed() {
+        return;
+    }
+    for mut transform in &mut reference_frame {
+        *transform = ego_pose.0;
+    }
+}
+
+type ColoredLine = (Vec3, Vec3, Color);
+
+fn line_mesh(lines: Vec<ColoredLine>) -> Mesh {
+    let positions: Vec<[f32; 3]> = lines
+        .iter()
+        .flat_map(|(start, end, _)| [start.to_array(), end.to_array()])
+        .collect();
+    let colors: Vec<[f32; 4]> = lines
+        .iter()
+        .flat_map(|(_, _, color)| [color.as_linear_rgba_f32(); 2])
+        .collect();
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let indices = (0..positions.len() as u32).collect();
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+fn grid_lines(spacing: f32, extent: f32, height: f32) -> Vec<ColoredLine> {
+    if spacing <= 0.0 || extent <= 0.0 {
+        return Vec::new();
+    }
+    let color = Color::rgba(0.5, 0.5, 0.5, 1.0);
+    let half_line_count = (extent / spacing).floor() as i32;
+    let half_extent = half_line_count as f32 * spacing;
+    (-half_line_count..=half_line_count)
+        .flat_map(|iter| {
+            let offset = iter as f32 * spacing;
+            [
+                (
+                    Vec3::new(offset, height, -half_extent),
+                    Vec3::new(offset, height, half_extent),
+                    color,
+                ),
+                (
+                    Vec3::new(-half_extent, height, offset),
+                    Vec3::new(half_extent, height, offset),
+                    color,
+                ),
+            ]
+        })
+        .collect()
+}
+
+// sensor axes x, y and z are drawn red, green and blue, sensor y and z are swapped in bevy
+fn axes_lines() -> Vec<ColoredLine> {
+    vec![
+        (Vec3::ZERO, Vec3::X * AXES_LENGTH, Color::RED),
+        (Vec3::ZERO, Vec3::Z * AXES_LENGTH, Color::GREEN),
+        (Vec3::ZERO, Vec3::Y * AXES_LENGTH, Color::BLUE),
+    ]
+}
+
+fn range_ring_lines(spacing: f32, count: u32, height: f32) -> Vec<ColoredLine> {
+    if spacing <= 0.0 {
+        return Vec::new();
+    }
+    let color = Color::rgb(0.8, 0.8, 0.3);
+    let point_on_ring = |radius: f32, segment: usize| {
+        let angle = segment as f32 / RANGE_RING_SEGMENTS as f32 * std::f32::consts::TAU;
+        Vec3::new(radius * angle.cos(), height, radius * angle.sin())
+    };
+    (1..=count)
+        .flat_map(|ring| {
+            let radius = ring as f32 * spacing;
+            (0..RANGE_RING_SEGMENTS).map(move |segment| {
+                (
+                    point_on_ring(radius, segment),
+                    point_on_ring(radius, segment + 1),
+                    color,
+                )
+            })
+        })
+        .collect()
+}
+
+fn draw_range_ring_labels(
+    mut egui_context: EguiContexts,
+    config: Res<PlayerConfig>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    reference_frame: Query<&GlobalTransform, With<ReferenceFrame>>,
+) {
+    let config = &config.persistent;
+    if !config.range_rings_visible || config.range_ring_spacing <= 0.0 {
+        return;
+    }
+    let Ok(reference_transform) = reference_frame.get_single() else {
+        return;
+    };
+    let painter = egui_context
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+    for (camera, camera_transform) in &cameras {
+        if !camera.is_active {
+            continue;
+        }
+        for ring in 1..=config.range_ring_count {
+            let radius = ring as f32 * config.range_ring_spacing;
+            let position =
+                reference_transform.transform_point(Vec3::new(radius, config.grid_height, 0.0));
+            if let Some(label_position) = world_to_egui(camera, camera_transform, position) {
+                painter.text(
+                    label_position,
+                    egui::Align2::CENTER_BOTTOM,
+                    format!("{radius} m"),
+                    egui::FontId::proportional(14.0),
+                    egui::Color32::from_rgb(204, 204, 76),
+                );
+            }
+        }
+    }
+}
+
+/// Projects a world position into egui coordinates, which have their origin in the upper left corner.
+/// Positions outside of the viewport are dropped, they would be drawn over other viewports.
+pub fn world_to_egui(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    world_position: Vec3,
+) -> Option<egui::Pos2> {
+    let viewport_position = camera.world_to_viewport(camera_transform, world_position)?;
+    let (viewport_min, viewport_max) = camera.logical_viewport_rect()?;
+    let position = egui::pos2(
+        viewport_min.x + viewport_position.x,
+        viewport_max.y - viewport_position.y,
+    );
+    egui::Rect::from_min_max(
+        egui::pos2(viewport_min.x, viewport_min.y),
+        egui::pos2(viewport_max.x, viewport_max.y),
+    )
+    .contains(position)
+    .then_some(position)
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy_egui::{egui::*, *};
+
+use super::ui_plugin::UiState;
+use crate::plugins::{lidar::PlayerState, Notification, TimelineAnnotations};
+
+pub fn panel(
+    mut egui_context: EguiContexts,
+    ui_state: Res<UiState>,
+    mut annotations: ResMut<TimelineAnnotations>,
+    mut player: ResMut<PlayerState>,
+    mut new_text: Local<String>,
+    mut notifications: EventWriter<Notification>,
+) {
+    if !ui_state.annotations_visible {
+        return;
+    }
+    let ctx = egui_context.ctx_mut();
+    let panel_frame = egui::Frame {
+        fill: Color32::from_rgba_premultiplied(10, 10, 10, 200),
+        inner_margin: style::Margin::same(6.0),
+        ..egui::Frame::default()
+    };
+    egui::SidePanel::right("Annotations")
+        .frame(panel_frame)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.heading("Annotations");
+            let mut indexes_to_remove = Vec::new();
+            let mut edited = false;
+            let remove_button_color = Color32::from_rgb(60, 60, 60);
+            egui::ScrollArea::vertical()
+                .max_height(ui.available_height() - 110.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("Annotation-Grid")
+                        .striped(true)
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for (index, annotation) in
+                                annotations.annotations.iter_mut().enumerate()
+                            {
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .button(RichText::new("✖").color(remove_button_color))
+                                        .clicked()
+                                    {
+                                        indexes_to_remove.push(index);
+                                    }
+                                    let text = match annotation.frame {
+                                        Some(frame) => format!("Frame {frame}"),
+                                        None => annotation.frame_id.clone(),
+                                    };
+                                    if ui
+                                        .add_enabled(
+                                            annotation.frame.is_some(),
+                                            egui::Button::new(text),
+                                        )
+                                        .on_hover_text(format!("Go to {}", annotation.frame_id))
+                                        .on_disabled_hover_text("Missing in the sequence")
+                                        .clicked()
+                                    {
+                                        if let Some(frame) = annotation.frame {
+                                            player.request_frame(frame);
+                                        }
+                                    }
+                                });
+                                edited |= ui.text_edit_singleline(&mut annotation.text).changed();
+                                ui.end_row();
+                            }
+                        });
+                });
+            if edited {
+                annotations.mark_modified();
+            }
+            for index in indexes_to_remove.into_iter().rev() {
+                annotations.remove(index);
+            }
+            ui.separator();
+            let frame = player.get_frame();
+            let frame_id = player.get_frame_id(frame).map(|id| id.to_string());
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut *new_text);
+                let add_button = ui.add_enabled(
+                    frame_id.is_some(),
+                    egui::Button::new(format!("➕ at frame {frame}")),
+                );
+                if let (true, Some(frame_id)) = (add_button.clicked(), frame_id) {
+                    let text = match new_text.trim().is_empty() {
+                        true => format!("Frame {frame}"),
+                        false => new_text.trim().to_string(),
+                    };
+                    annotations.insert(frame, frame_id, text);
+                    new_text.clear();
+                }
+            });
+            ui.horizontal(|ui| {
+                let save_text = match annotations.is_modified() {
+                    true => "Save*",
+                    false => "Save",
+                };
+                if ui.button(save_text).clicked() {
+                    if let Err(error) = annotations.save() {
+                        notifications.send(Notification::error(error));
+                    }
+                }
+                if ui.button("Reload").clicked() {
+                    match annotations.reload(&player) {
+                        Ok(()) => notifications.send_batch(annotations.missing_frames_warning()),
+                        Err(error) => notifications.send(Notification::error(error)),
+                    }
+                }
+            });
+            if let Some(file_path) = annotations.file_path() {
+                ui.label(RichText::new(file_path.to_string_lossy()).weak());
+            }
+        });
+}
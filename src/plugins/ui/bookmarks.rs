@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use bevy_egui::{egui::*, *};
+
+use super::ui_plugin::UiState;
+use crate::plugins::{CameraEvent, PlayerConfig};
+
+pub fn window(
+    mut egui_context: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut config: ResMut<PlayerConfig>,
+    mut camera_events: EventWriter<CameraEvent>,
+) {
+    let ctx = egui_context.ctx_mut();
+    egui::Window::new("Camera-Bookmarks")
+        .open(&mut ui_state.bookmarks_visible)
+        .resizable(true)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            let mut request_save = false;
+            let mut indexes_to_remove = Vec::new();
+            let remove_button_color = Color32::from_rgb(60, 60, 60);
+            ui.label(RichText::new("Recall with 1-9, save with Ctrl + 1-9").weak());
+            egui::Grid::new("Numbered-Bookmark-Grid")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for (slot, numbered) in
+                        config.persistent.numbered_bookmarks.iter_mut().enumerate()
+                    {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(
+                                    numbered.is_some(),
+                                    egui::Button::new(
+                                        RichText::new("✖").color(remove_button_color),
+                                    ),
+                                )
+                                .clicked()
+                            {
+                                *numbered = None;
+                                request_save = true;
+                            }
+                            ui.label((slot + 1).to_string());
+                        });
+                        match numbered {
+                            Some(bookmark) => {
+                                if ui.text_edit_singleline(&mut bookmark.name).changed() {
+                                    request_save = true;
+                                }
+                            }
+                            None => {
+                                ui.label(RichText::new("Empty").weak());
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(numbered.is_some(), egui::Button::new("Go"))
+                                .clicked()
+                            {
+                                camera_events.send(CameraEvent::RecallNumberedBookmark(slot));
+                            }
+                            if ui
+                                .button("Update")
+                                .on_hover_text("Replace with the current view")
+                                .clicked()
+                            {
+                                camera_events.send(CameraEvent::SaveNumberedBookmark(slot));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+            egui::Grid::new("Bookmark-Grid")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for (index, bookmark) in
+                        config.persistent.camera_bookmarks.iter_mut().enumerate()
+                    {
+                        if ui
+                            .button(RichText::new("✖").color(remove_button_color))
+                            .clicked()
+                        {
+                            indexes_to_remove.push(index);
+                            request_save = true;
+                        }
+                        if ui.text_edit_singleline(&mut bookmark.name).changed() {
+                            request_save = true;
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Go").clicked() {
+                                camera_events.send(CameraEvent::RecallBookmark(index));
+                            }
+                            if ui
+                                .button("Update")
+                                .on_hover_text("Replace with the current view")
+                                .clicked()
+                            {
+                                camera_events.send(CameraEvent::SaveBookmark {
+                                    index: Some(index),
+                                    name: bookmark.name.clone(),
+                                });
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            if ui
+                .button("➕")
+                .on_hover_text("Add the current view")
+                .clicked()
+            {
+                let name = format!("View {}", config.persistent.camera_bookmarks.len() + 1);
+                camera_events.send(CameraEvent::SaveBookmark { index: None, name });
+            }
+            for index in indexes_to_remove.into_iter().rev() {
+                config.persistent.camera_bookmarks.remove(index);
+            }
+            if request_save {
+                config.save();
+            }
+        });
+}
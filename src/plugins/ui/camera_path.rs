@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_egui::{egui::*, *};
+
+use super::ui_plugin::UiState;
+use crate::plugins::{lidar::PlayerState, CameraPath, Notification, Viewport};
+
+pub fn window(
+    mut egui_context: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut camera_path: ResMut<CameraPath>,
+    mut player: ResMut<PlayerState>,
+    cameras: Query<(&Transform, &Viewport)>,
+    mut notifications: EventWriter<Notification>,
+) {
+    let ctx = egui_context.ctx_mut();
+    egui::Window::new("Camera-Path")
+        .open(&mut ui_state.camera_path_visible)
+        .resizable(true)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            let mut indexes_to_remove = Vec::new();
+            let mut keyframe_to_update = None;
+            let remove_button_color = Color32::from_rgb(60, 60, 60);
+            ui.checkbox(&mut camera_path.active, "Play Camera Path")
+                .on_hover_text("The camera follows the keyframes during playback");
+            egui::Grid::new("Camera-Path-Grid")
+                .striped(true)
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (index, keyframe) in camera_path.keyframes.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button(RichText::new("✖").color(remove_button_color))
+                                .clicked()
+                            {
+                                indexes_to_remove.push(index);
+                            }
+                            if ui
+                                .button(format!("Frame {}", keyframe.frame))
+                                .on_hover_text("Go to frame")
+                                .clicked()
+                            {
+                                player.request_frame(keyframe.frame);
+                            }
+                        });
+                        if ui
+                            .button("Update")
+                            .on_hover_text("Replace with the current view")
+                            .clicked()
+                        {
+                            keyframe_to_update = Some(keyframe.frame);
+                        }
+                        ui.end_row();
+                    }
+                });
+            let frame = player.get_frame();
+            if ui.button(format!("➕ Keyframe at frame {frame}")).clicked() {
+                keyframe_to_update = Some(frame);
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    if let Err(error) = camera_path.save() {
+                        notifications.send(Notification::error(error));
+                    }
+                }
+                if ui.button("Reload").clicked() {
+                    if let Err(error) = camera_path.reload() {
+                        notifications.send(Notification::error(error));
+                    }
+                }
+            });
+            if let Some(file_path) = camera_path.file_path() {
+                ui.label(RichText::new(file_path.to_string_lossy()).weak());
+            }
+            for index in indexes_to_remove.into_iter().rev() {
+                camera_path.keyframes.remove(index);
+            }
+            let main_camera = cameras.iter().find(|(_, viewport)| viewport.is_main());
+            if let (Some(frame), Some((transform, _))) = (keyframe_to_update, main_camera) {
+                camera_path.insert_keyframe(frame, transform);
+            }
+        });
+}
@@ -0,0 +1,65 @@
+use bevy_egui::egui::{pos2, vec2, Color32, Rect, Response, Sense, Ui, Widget};
+
+/// Strip below the timeline, each column is colored by the highest count of the frames it covers.
+pub struct HeatStrip<'a> {
+    counts: &'a [u32],
+    max_frame: usize,
+    height: f32,
+}
+
+impl<'a> HeatStrip<'a> {
+    pub fn new(counts: &'a [u32], max_frame: usize) -> Self {
+        Self {
+            counts,
+            max_frame,
+            height: 6.0,
+        }
+    }
+
+    /// Frame under a horizontal position of the strip.
+    pub fn frame_at(rect: &Rect, max_frame: usize, x: f32) -> usize {
+        let normalized = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+        (normalized * max_frame as f32).round() as usize
+    }
+
+    fn heat_color(heat: f32) -> Color32 {
+        Color32::from_rgb(
+            (120.0 + 135.0 * heat) as u8,
+            (220.0 * heat) as u8,
+            (40.0 * heat) as u8,
+        )
+    }
+}
+
+impl Widget for HeatStrip<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let desired_size = vec2(ui.spacing().slider_width, self.height);
+        let (rect, response) = ui.allocate_exact_size(desired_size, Sense::click_and_drag());
+        if ui.is_rect_visible(rect) && !self.counts.is_empty() {
+            let painter = ui.painter();
+            painter.rect_filled(rect, 0.0, Color32::from_rgb(30, 30, 30));
+            // logarithmic, a few points of a rare class stay visible next to crowded frames
+            let max_heat = (self.counts.iter().copied().max().unwrap_or(0) as f32).ln_1p();
+            let last_count = self.counts.len() - 1;
+            for column in 0..rect.width().ceil() as usize {
+                let left = rect.left() + column as f32;
+                let first_frame = Self::frame_at(&rect, self.max_frame, left).min(last_count);
+                let last_frame = Self::frame_at(&rect, self.max_frame, left + 1.0)
+                    .clamp(first_frame, last_count);
+                let count = self.counts[first_frame..=last_frame]
+                    .iter()
+                    .copied()
+                    .max()
+                    .unwrap_or(0);
+                if count == 0 || max_heat <= 0.0 {
+                    continue;
+                }
+                let heat = (count as f32).ln_1p() / max_heat;
+                let column_rect =
+                    Rect::from_min_max(pos2(left, rect.top()), pos2(left + 1.0, rect.bottom()));
+                painter.rect_filled(column_rect, 0.0, Self::heat_color(heat));
+            }
+        }
+        response
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy_egui::{*, egui::RichText};
-use crate::{io::{Point, Label}, plugins::{PlayerConfig, lidar::PlayerState}};
+use crate::{io::{Point, Label}, plugins::{cursor_to_viewport, PlayerConfig, Viewport, lidar::{EgoPose, PlayerState}}};
 
 #[derive(Resource, Default)]
 pub struct Inspector{
@@ -59,7 +59,7 @@ impl Inspector{
     }
     pub fn detect_point_under_curser(
         player: Res<PlayerState>,
-        cameras: Query<(&Camera, &GlobalTransform)>,
+        cameras: Query<(&Camera, &GlobalTransform, &Viewport)>,
         config: Res<PlayerConfig>,
         mut query_window: Query<&mut Window>,
         mut inspector: ResMut<Self>
@@ -68,37 +68,26 @@ impl Inspector{
         if !inspector.visible{
             return;
         }
-        let Some(frame) = player.get_frame_content() else {
+        let window = query_window.single_mut();
+        let Some(cursor_position) = window.cursor_position() else {
             return;
         };
-        let window = query_window.single_mut();
-        let Some(mouse_position) = window.cursor_position() else {
+        let Some((camera, transform, viewport)) = cameras.iter().find(|(.., viewport)| viewport.active) else {
+            return;
+        };
+        let slot = viewport.sequence_slot(player.is_comparing());
+        let Some(frame) = player.get_frame_content_of(slot) else {
+            return;
+        };
+        let Some(mouse_position) = cursor_to_viewport(camera, &window, cursor_position) else {
             return;
         };
-        let (camera, transform) = cameras.single();
         let Some(ray) = camera.viewport_to_world(transform, mouse_position) else {
             return;
         };
 
-        //Assume all points are sphere with a radius
-        let mut min_distance = f32::MAX;
-        let mut min_index = None;
-        let radius_squared = config.persistent.point_size * config.persistent.point_size;
-        for (iter, point) in frame.points.iter().enumerate(){
-            let sphere_pos_to_ray_pos = point.position - ray.origin;
-            let projected_distance = sphere_pos_to_ray_pos.dot(ray.direction);
-            if projected_distance < 0.0 {
-                continue;
-            }
-            let distance_to_ray_squared = sphere_pos_to_ray_pos.length_squared() - projected_distance * projected_distance;
-            if distance_to_ray_squared > radius_squared {
-                continue;
-            }
-            if projected_distance < min_distance {
-                min_distance = projected_distance;
-                min_index = Some(iter);
-            }
-        }
+        let pose = EgoPose(player.get_pose_of(slot));
+        let min_index = frame.pick_point(pose.ray_to_sensor(ray), config.persistent.point_size);
         if let Some(index) = min_index {
             inspector.point = Some(frame.points[index].clone());
             inspector.label = frame.labels.as_ref().map(|labels| labels[index]);
@@ -1,11 +1,17 @@
 mod ui_plugin;
 mod video_slider;
+mod heat_strip;
 mod request;
 mod shortcut;
 mod task;
 mod image;
 mod settings;
 mod inspector;
+mod bookmarks;
+mod camera_path;
+mod annotations;
+mod validation;
+mod notifications;
 use inspector::Inspector;
 
 pub use ui_plugin::UiPlugin;
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use bevy_egui::{egui::*, *};
+
+use super::ui_plugin::UiState;
+use crate::plugins::{NotificationHistory, NotificationLevel};
+
+pub fn window(
+    mut egui_context: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut history: ResMut<NotificationHistory>,
+    mut errors_only: Local<bool>,
+) {
+    let ctx = egui_context.ctx_mut();
+    egui::Window::new("Notifications")
+        .open(&mut ui_state.notifications_visible)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut errors_only, "Errors only");
+                if ui.button("Clear").clicked() {
+                    history.entries.clear();
+                }
+            });
+            ui.separator();
+            if history.entries.is_empty() {
+                ui.label(RichText::new("No notifications.").weak());
+                return;
+            }
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("Notification-Grid")
+                        .striped(true)
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            let entries = history.entries.iter().filter(|entry| {
+                                !*errors_only || entry.level == NotificationLevel::Error
+                            });
+                            for entry in entries {
+                                ui.label(RichText::new(format!("{:.1}s", entry.time)).weak());
+                                ui.label(
+                                    RichText::new(entry.level.name()).color(entry.level.color()),
+                                );
+                                ui.label(&entry.text);
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+}
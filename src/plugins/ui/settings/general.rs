@@ -2,7 +2,10 @@ use bevy::prelude::*;
 use bevy_egui::*;
 
 use super::super::ui_plugin::UiState;
-use crate::plugins::{config::PlayerConfig, lidar::PlayerState};
+use crate::plugins::{
+    config::{CameraFollow, CameraMode, ColorMode, Config, FrameErrorPolicy, PlayerConfig},
+    lidar::PlayerState,
+};
 
 pub fn window(
     mut egui_context: EguiContexts,
@@ -26,7 +29,43 @@ pub fn window(
             ui.label("Default Label Color");
             if ui.color_edit_button_srgb(&mut config.persistent.default_color).changed() {
                 config.update_label_map();
-                player.request_update();
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Color Mode");
+            let mut color_mode = config.persistent.color_mode;
+            egui::ComboBox::from_id_source("Color-Mode").selected_text(color_mode.name()).show_ui(ui, |ui| {
+                for mode in ColorMode::ALL {
+                    ui.selectable_value(&mut color_mode, mode, mode.name());
+                }
+            });
+            if color_mode != config.persistent.color_mode {
+                config.persistent.color_mode = color_mode;
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Point Opacity");
+            if ui.add(egui::Slider::new(&mut config.persistent.point_opacity, 0.0..=1.0)).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Hidden Label Opacity");
+            if ui.add(egui::Slider::new(&mut config.persistent.hidden_label_opacity, 0.0..=1.0)).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Eye-Dome Lighting");
+            if ui.checkbox(&mut config.persistent.edl_enabled, "").on_hover_text("Only shades opaque points, it is off while an opacity is below 1").changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("EDL Strength");
+            if ui.add_enabled(config.persistent.edl_enabled, egui::Slider::new(&mut config.persistent.edl_strength, 0.0..=5.0)).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("EDL Radius");
+            if ui.add_enabled(config.persistent.edl_enabled, egui::Slider::new(&mut config.persistent.edl_radius, 0.5..=5.0).suffix("px")).changed() {
                 config.save();
             }
             ui.end_row();
@@ -38,6 +77,85 @@ pub fn window(
                 config.save();
             }
             ui.end_row();
+            ui.label("Size Attenuation");
+            if ui.checkbox(&mut config.persistent.size_attenuation, "").on_hover_text("Points shrink with the distance but stay within the pixel sizes, otherwise they keep their size in meters").changed() {
+                config.save();
+            }
+            ui.end_row();
+            if config.persistent.size_attenuation {
+                let max_point_pixels = config.persistent.max_point_pixels;
+                ui.label("Min Point Size");
+                if ui.add(egui::DragValue::new(&mut config.persistent.min_point_pixels).clamp_range(0.0..=max_point_pixels).speed(0.1).suffix("px")).changed() {
+                    config.save();
+                }
+                ui.end_row();
+                let min_point_pixels = config.persistent.min_point_pixels;
+                ui.label("Max Point Size");
+                if ui.add(egui::DragValue::new(&mut config.persistent.max_point_pixels).clamp_range(min_point_pixels..=100.0).speed(0.1).suffix("px")).changed() {
+                    config.save();
+                }
+                ui.end_row();
+            }
+            ui.label("Level of Detail");
+            if ui.checkbox(&mut config.persistent.lod_enabled, "").on_hover_text("Downsample far away points").changed() {
+                player.request_update();
+                config.save();
+            }
+            ui.end_row();
+            ui.label("LOD Distance");
+            if ui.add_enabled(config.persistent.lod_enabled, egui::DragValue::new(&mut config.persistent.lod_distance).clamp_range(0.0..=1000.0).speed(0.5).suffix("m")).changed() {
+                player.request_update();
+                config.save();
+            }
+            ui.end_row();
+            ui.label("LOD Voxel Size");
+            if ui.add_enabled(config.persistent.lod_enabled, egui::DragValue::new(&mut config.persistent.lod_voxel_size).clamp_range(0.01..=10.0).speed(0.01).suffix("m")).changed() {
+                player.request_update();
+                config.save();
+            }
+            ui.end_row();
+            ui.end_row();
+            ui.label("Ground Grid");
+            if ui.checkbox(&mut config.persistent.grid_visible, "").changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Grid Spacing");
+            if ui.add_enabled(config.persistent.grid_visible, egui::DragValue::new(&mut config.persistent.grid_spacing).clamp_range(0.1..=100.0).speed(0.1).suffix("m")).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Grid Extent");
+            if ui.add_enabled(config.persistent.grid_visible, egui::DragValue::new(&mut config.persistent.grid_extent).clamp_range(1.0..=1000.0).speed(1.0).suffix("m")).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Ground Height");
+            if ui.add(egui::DragValue::new(&mut config.persistent.grid_height).clamp_range(-100.0..=100.0).speed(0.01).suffix("m")).on_hover_text("Height of grid and range rings in the sensor frame").changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Axes");
+            if ui.checkbox(&mut config.persistent.axes_visible, "").on_hover_text("X red, Y green, Z blue").changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Range Rings");
+            if ui.checkbox(&mut config.persistent.range_rings_visible, "").changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Ring Spacing");
+            if ui.add_enabled(config.persistent.range_rings_visible, egui::DragValue::new(&mut config.persistent.range_ring_spacing).clamp_range(0.5..=500.0).speed(0.5).suffix("m")).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Ring Count");
+            if ui.add_enabled(config.persistent.range_rings_visible, egui::DragValue::new(&mut config.persistent.range_ring_count).clamp_range(1..=100)).changed() {
+                config.save();
+            }
+            ui.end_row();
+            ui.end_row();
             ui.label("Sensor FPS");
             if ui.add(egui::DragValue::new(&mut config.persistent.sensor_fps).clamp_range(0.0..=10000.0).speed(1.0)).changed() {
                 player.set_sensor_fps(config.persistent.sensor_fps);
@@ -45,6 +163,54 @@ pub fn window(
                 config.save();
             }
             ui.end_row();
+            ui.label("Failed Frames");
+            let mut frame_error_policy = config.persistent.frame_error_policy;
+            egui::ComboBox::from_id_source("Frame-Error-Policy").selected_text(frame_error_policy.name()).show_ui(ui, |ui| {
+                for policy in FrameErrorPolicy::ALL {
+                    ui.selectable_value(&mut frame_error_policy, policy, policy.name());
+                }
+            }).response.on_hover_text("Skip frames that cannot be read or stop the playback on them");
+            if frame_error_policy != config.persistent.frame_error_policy {
+                config.persistent.frame_error_policy = frame_error_policy;
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Memory Budget");
+            if ui.add(egui::DragValue::new(&mut config.persistent.memory_budget_mb).clamp_range(Config::MEMORY_BUDGET_RANGE_MB).speed(16.0).suffix(" MB")).on_hover_text("Memory of the loaded frames, the least recently used frames are freed first").changed() {
+                player.set_memory_budget(config.persistent.memory_budget_mb);
+                config.save();
+            }
+            ui.end_row();
+            ui.end_row();
+            ui.label("Camera Mode");
+            let mut camera_mode = config.persistent.camera_mode;
+            egui::ComboBox::from_id_source("Camera-Mode").selected_text(camera_mode.name()).show_ui(ui, |ui| {
+                for mode in CameraMode::ALL {
+                    ui.selectable_value(&mut camera_mode, mode, mode.name());
+                }
+            }).response.on_hover_text("Orbit: right drag rotates, middle drag pans, scroll zooms, double click sets the pivot\nBird's-Eye View: right or middle drag pans, scroll zooms");
+            if camera_mode != config.persistent.camera_mode {
+                config.persistent.camera_mode = camera_mode;
+                config.save();
+            }
+            ui.end_row();
+            ui.label("World Coordinates");
+            if ui.add_enabled(player.has_poses(), egui::Checkbox::new(&mut config.persistent.world_coordinates, "")).on_hover_text("Places the points with the poses.txt of the sequence").on_disabled_hover_text("The sequence has no poses.txt").changed() {
+                player.request_update();
+                config.save();
+            }
+            ui.end_row();
+            ui.label("Camera Follow");
+            let mut camera_follow = config.persistent.camera_follow;
+            egui::ComboBox::from_id_source("Camera-Follow").selected_text(camera_follow.name()).show_ui(ui, |ui| {
+                for follow in CameraFollow::ALL {
+                    ui.selectable_value(&mut camera_follow, follow, follow.name());
+                }
+            }).response.on_hover_text("Keeps the camera offset to the ego vehicle");
+            if camera_follow != config.persistent.camera_follow {
+                config.persistent.camera_follow = camera_follow;
+                config.save();
+            }
             ui.end_row();
             ui.label("Camera Speed");
             if ui.add(egui::Slider::new(&mut config.persistent.camera_speed, 0.0..=100.0)).changed() {
@@ -54,10 +220,9 @@ pub fn window(
             ui.label("Camera FOV");
             if ui.add(egui::Slider::new(&mut config.persistent.camera_fov_degreas, 0.0..=180.0)).changed() {
                 for mut projection in query_projection.iter_mut(){
-                    *projection = Projection::Perspective(PerspectiveProjection {
-                        fov: config.persistent.camera_fov_degreas.to_radians(),
-                        ..default()
-                    });
+                    if let Projection::Perspective(perspective) = projection.as_mut() {
+                        perspective.fov = config.persistent.camera_fov_degreas.to_radians();
+                    }
                 }
                 config.save();
             }
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+use bevy_egui::{egui::*, *};
+
+use super::super::ui_plugin::UiState;
+use crate::plugins::{Action, KeyBinding, PlayerConfig};
+
+const MODIFIER_KEYS: [KeyCode; 4] = [
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+];
+
+pub fn window(
+    mut egui_context: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    mut config: ResMut<PlayerConfig>,
+    input: Res<Input<KeyCode>>,
+    mut capturing_action: Local<Option<Action>>,
+) {
+    // the key is taken from bevy, egui doesn't know all keys
+    let mut captured = false;
+    if let Some(action) = *capturing_action {
+        let pressed_key = input
+            .get_just_pressed()
+            .copied()
+            .find(|key| !MODIFIER_KEYS.contains(key) || action == Action::Run);
+        match pressed_key {
+            Some(KeyCode::Escape) => *capturing_action = None,
+            Some(key) => {
+                let modifier = |keys: [KeyCode; 2]| input.any_pressed(keys) && !keys.contains(&key);
+                config.persistent.key_bindings.set(
+                    action,
+                    KeyBinding {
+                        key,
+                        shift: modifier([KeyCode::LShift, KeyCode::RShift]),
+                        control: modifier([KeyCode::LControl, KeyCode::RControl]),
+                    },
+                );
+                config.save();
+                *capturing_action = None;
+                captured = true;
+            }
+            None => {}
+        }
+    }
+
+    let ctx = egui_context.ctx_mut();
+    let mut visible = ui_state.key_bindings_visible;
+    egui::Window::new("Key-Bindings")
+        .open(&mut visible)
+        .resizable(true)
+        .vscroll(true)
+        .show(ctx, |ui| {
+            egui::Grid::new("Key-Grid")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for action in Action::all() {
+                        ui.label(action.name());
+                        let text = match *capturing_action == Some(action) {
+                            true => "Press a key...".to_string(),
+                            false => config.persistent.key_bindings.label(action),
+                        };
+                        if ui
+                            .button(text)
+                            .on_hover_text("Click and press the new key, Escape cancels")
+                            .clicked()
+                        {
+                            *capturing_action = Some(action);
+                        }
+                        let conflicts = config.persistent.key_bindings.conflicts(action);
+                        if conflicts.is_empty() {
+                            ui.label("");
+                        } else {
+                            let names: Vec<_> =
+                                conflicts.iter().map(|action| action.name()).collect();
+                            ui.label(RichText::new("⚠").color(Color32::from_rgb(255, 80, 80)))
+                                .on_hover_text(format!("Same key as: {}", names.join(", ")));
+                        }
+                        ui.end_row();
+                    }
+                });
+            ui.horizontal(|ui| {
+                if ui
+                    .button(RichText::from("↺").heading())
+                    .on_hover_text("Reset all key bindings")
+                    .clicked()
+                {
+                    config.persistent.key_bindings.reset();
+                    config.save();
+                }
+            });
+        });
+    if !visible {
+        *capturing_action = None;
+    }
+    ui_state.key_bindings_visible = visible;
+    // also block the shortcuts in the frame the key was captured
+    ui_state.capturing_key = capturing_action.is_some() || captured;
+}
@@ -1,4 +1,4 @@
-use crate::plugins::{lidar::PlayerState, *};
+use crate::plugins::*;
 
 use super::super::ui_plugin::UiState;
 use bevy::prelude::*;
@@ -11,7 +11,7 @@ pub struct NewLabel {
 }
 impl Default for NewLabel{
     fn default() -> Self {
-        Self { id: 0, info: LabelInfo{color: [255,255,255], name: default()} }
+        Self { id: 0, info: LabelInfo{color: [255,255,255], name: default(), hidden: false, heatmap: false} }
     }
 }
 
@@ -26,7 +26,6 @@ pub fn window(
     mut egui_context: EguiContexts,
     mut ui_state: ResMut<UiState>,
     mut config: ResMut<PlayerConfig>,
-    mut player: ResMut<PlayerState>,
     mut new_label: ResMut<NewLabel>,
 ) {
     let ctx = egui_context.ctx_mut();
@@ -58,9 +57,28 @@ pub fn window(
                     request_color_update = true;
                     request_save = true;
                 }
-                if ui.text_edit_singleline(&mut info.name).changed() {
-                    request_save = true;
-                }
+                ui.horizontal(|ui| {
+                    if ui.text_edit_singleline(&mut info.name).changed() {
+                        request_save = true;
+                    }
+                    let visibility_text = RichText::new("👁").color(match info.hidden {
+                        true => remove_button_color,
+                        false => Color32::WHITE,
+                    });
+                    if ui.button(visibility_text).on_hover_text("Show / Hide").clicked() {
+                        info.hidden = !info.hidden;
+                        request_color_update = true;
+                        request_save = true;
+                    }
+                    let heatmap_text = RichText::new("🔥").color(match info.heatmap {
+                        true => Color32::WHITE,
+                        false => remove_button_color,
+                    });
+                    if ui.button(heatmap_text).on_hover_text("Count in the timeline heatmap").clicked() {
+                        info.heatmap = !info.heatmap;
+                        request_save = true;
+                    }
+                });
                 ui.end_row();
             }
             ui.horizontal(|ui| {
@@ -85,7 +103,6 @@ pub fn window(
         ui.horizontal(|ui| {
             if ui.button(RichText::from("↺").heading()).on_hover_text("Reset all labels").clicked() {
                 config.reset_label_map();
-                player.request_update();
                 request_save = true;
             }
         });
@@ -94,7 +111,6 @@ pub fn window(
         }
         if request_color_update {
             config.update_label_map();
-            player.request_update();
         }
         if request_save {
             config.save()
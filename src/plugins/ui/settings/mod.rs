@@ -1,2 +1,3 @@
 pub mod label;
-pub mod general;
\ No newline at end of file
+pub mod general;
+pub mod keys;
\ No newline at end of file
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy_egui::EguiContexts;
-use crate::plugins::lidar::*;
+use crate::plugins::{lidar::*, Action, CameraEvent, PlayerConfig};
 use super::{ui_plugin::UiState, inspector::Inspector};
 
 
@@ -9,34 +9,103 @@ pub fn handle_shortcuts(
     mut player: ResMut<PlayerState>, 
     mut ui_state: ResMut<UiState>,
     mut inspector: ResMut<Inspector>,
+    mut config: ResMut<PlayerConfig>,
+    mut camera_events: EventWriter<CameraEvent>,
     mut egui_ctx: EguiContexts,
 ){
     if egui_ctx.ctx_mut().memory(|memory| memory.focus().is_some()) {
         return;
     }
-    if input.just_pressed(KeyCode::F) || input.just_pressed(KeyCode::F12) {
+    if ui_state.capturing_key {
+        return;
+    }
+    let bindings = &config.persistent.key_bindings;
+    if bindings.just_pressed(Action::Fullscreen, &input)
+        || bindings.just_pressed(Action::FullscreenAlternative, &input)
+    {
         ui_state.fullscreen.request();
     }
-    if input.just_pressed(KeyCode::I) {
+    if bindings.just_pressed(Action::Inspector, &input) {
         inspector.visible = !inspector.visible;
     }
-    if input.just_pressed(KeyCode::Space) {
+    if bindings.just_pressed(Action::SwitchCameraMode, &input) {
+        camera_events.send(CameraEvent::SwitchMode);
+    }
+    if bindings.just_pressed(Action::FrameAll, &input) {
+        camera_events.send(CameraEvent::FrameAll);
+    }
+    if bindings.just_pressed(Action::ResetCamera, &input) {
+        camera_events.send(CameraEvent::Reset);
+    }
+    if bindings.just_pressed(Action::PlayPause, &input) {
         player.toggle_play();
     }
-    if input.pressed(KeyCode::Left) {
+    if bindings.pressed(Action::PreviousFrame, &input) {
         player.previous_frame();      
     }
-    if input.pressed(KeyCode::Right) {
+    if bindings.pressed(Action::NextFrame, &input) {
         player.next_frame();      
     }
-    if input.pressed(KeyCode::O) {
+    let switch_playback_mode = bindings.just_pressed(Action::SwitchPlaybackMode, &input);
+    if bindings.just_pressed(Action::SetRepeatStart, &input) {
+        let frame = player.get_frame();
+        player.set_repeat_start(frame);
+    }
+    if bindings.just_pressed(Action::SetRepeatEnd, &input) {
+        let frame = player.get_frame();
+        player.set_repeat_end(frame);
+    }
+    if bindings.just_pressed(Action::ClearRepeat, &input) {
+        player.clear_repeat();
+    }
+    if bindings.pressed(Action::OpenSequence, &input) {
         ui_state.folder_dialog.request();
     }
-    let shift = input.any_pressed([KeyCode::LShift, KeyCode::RShift]);
-    if input.pressed(KeyCode::L) {
-        match shift {
-            true => player.discard_labels(),
-            false => ui_state.label_folder_dialog.request(),
+    for slot in 0..Action::BOOKMARK_SLOTS {
+        if bindings.just_pressed(Action::RecallBookmark(slot), &input) {
+            camera_events.send(CameraEvent::RecallNumberedBookmark(slot));
         }
+        if bindings.just_pressed(Action::SaveBookmark(slot), &input) {
+            camera_events.send(CameraEvent::SaveNumberedBookmark(slot));
+        }
+    }
+    if bindings.pressed(Action::DiscardLabels, &input) {
+        player.discard_labels();
+    }
+    if bindings.pressed(Action::OpenLabels, &input) {
+        ui_state.label_folder_dialog.request();
+    }
+    // the bindings borrow the config until here
+    if switch_playback_mode {
+        let playback_mode = player.get_playback_mode().next();
+        player.set_playback_mode(playback_mode);
+        config.persistent.playback_mode = playback_mode;
+        config.save();
+    }
+}
+
+/// Face buttons play and switch the camera, the shoulder buttons step through the frames.
+pub fn handle_gamepad_shortcuts(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    mut player: ResMut<PlayerState>,
+    mut camera_events: EventWriter<CameraEvent>,
+){
+    let just_pressed = |button_type| {
+        gamepads
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    if just_pressed(GamepadButtonType::South) {
+        player.toggle_play();
+    }
+    if just_pressed(GamepadButtonType::North) {
+        camera_events.send(CameraEvent::SwitchMode);
+    }
+    if just_pressed(GamepadButtonType::LeftTrigger) {
+        player.previous_frame();
+    }
+    if just_pressed(GamepadButtonType::RightTrigger) {
+        player.next_frame();
     }
 }
\ No newline at end of file
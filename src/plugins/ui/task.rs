@@ -5,13 +5,15 @@ use rfd::*;
 use super::ui_plugin::UiState;
 use crate::{
     io,
-    plugins::{lidar::PlayerState, PlayerConfig},
+    plugins::{lidar::PlayerState, Notification, PlayerConfig, ViewportLayout},
 };
 
 #[derive(Copy, Clone)]
 pub enum FolderTaskType {
     Seqeunce,
     Label,
+    ComparisonSequence,
+    ComparisonLabel,
 }
 
 #[derive(Component)]
@@ -26,6 +28,7 @@ pub fn handle_load_folder_task(
     mut menu_state: ResMut<UiState>,
     mut player_state: ResMut<PlayerState>,
     mut config: ResMut<PlayerConfig>,
+    mut notifications: EventWriter<Notification>,
 ) {
     for (entity, mut folder_task) in &mut read_frame_tasks {
         let folder_type = folder_task.folder_type;
@@ -35,27 +38,47 @@ pub fn handle_load_folder_task(
                     FolderTaskType::Seqeunce => {
                         match io::read_sequence_from_dir(folder.path().into()) {
                             Ok(sequence) => {
+                                notifications.send_batch(sequence.pose_warning());
+                                notifications.send(Notification::info(format!(
+                                    "Opened sequence {:?} with {} frames.",
+                                    sequence.folder, sequence.frame_count
+                                )));
                                 player_state.set_sequence(sequence);
                                 config.persistent.folder_path =
                                     folder.path().to_str().map(|str| str.to_string());
                                 config.save();
                             }
                             Err(error) => {
-                                rfd::MessageDialog::new()
-                                    .set_title("Error")
-                                    .set_description(&format!("{error}"))
-                                    .set_buttons(rfd::MessageButtons::Ok)
-                                    .show();
+                                notifications.send(Notification::error(error));
                             }
                         }
                     }
                     FolderTaskType::Label => {
                         if let Err(error) = player_state.try_set_labels(folder.path().into()) {
-                                rfd::MessageDialog::new()
-                                    .set_title("Error")
-                                    .set_description(&format!("{error}"))
-                                    .set_buttons(rfd::MessageButtons::Ok)
-                                    .show();
+                            notifications.send(Notification::error(error));
+                        }
+                    }
+                    FolderTaskType::ComparisonSequence | FolderTaskType::ComparisonLabel => {
+                        let result = match folder_type {
+                            FolderTaskType::ComparisonSequence => {
+                                io::read_sequence_from_dir(folder.path().into()).map(|sequence| {
+                                    notifications.send_batch(sequence.pose_warning());
+                                    player_state.set_comparison_sequence(sequence)
+                                })
+                            }
+                            _ => player_state.try_set_comparison_labels(folder.path().into()),
+                        };
+                        match result {
+                            // the comparison needs a second viewport
+                            Ok(()) => {
+                                if config.persistent.viewport_layout == ViewportLayout::Single {
+                                    config.persistent.viewport_layout = ViewportLayout::Double;
+                                    config.save();
+                                }
+                            }
+                            Err(error) => {
+                                notifications.send(Notification::error(error));
+                            }
                         }
                     }
                 }
@@ -64,6 +87,10 @@ pub fn handle_load_folder_task(
             match folder_type {
                 FolderTaskType::Seqeunce => menu_state.folder_dialog.closed(),
                 FolderTaskType::Label => menu_state.label_folder_dialog.closed(),
+                FolderTaskType::ComparisonSequence => menu_state.comparison_folder_dialog.closed(),
+                FolderTaskType::ComparisonLabel => {
+                    menu_state.comparison_label_folder_dialog.closed()
+                }
             }
         }
     }
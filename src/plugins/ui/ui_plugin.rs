@@ -1,14 +1,15 @@
 use bevy::{prelude::*, window::WindowMode, app::AppExit};
 use bevy_egui::{
-    egui::{
-        epaint::Shadow, style::Margin, Color32, Key, KeyboardShortcut, Modifiers, RichText, Stroke,
-        Vec2,
-    },
+    egui::{epaint::Shadow, style::Margin, Color32, RichText, Stroke, Vec2},
     *,
 };
 
-use super::{image::*, request::*, video_slider::*, *};
-use crate::plugins::lidar;
+use super::{heat_strip::HeatStrip, image::*, request::*, video_slider::*, *};
+use crate::io::LoadState;
+use crate::plugins::{
+    lidar, Action, CameraEvent, CameraMode, LabelHeatmap, PlayerConfig, TimelineAnnotations,
+    ViewportLayout,
+};
 
 pub struct UiPlugin;
 impl Plugin for UiPlugin {
@@ -16,19 +17,31 @@ impl Plugin for UiPlugin {
         app.add_plugin(EguiPlugin)
             .insert_resource(UiState::default())
             .insert_resource(Inspector::default())
+            .insert_resource(validation::SequenceValidation::default())
             .insert_resource(settings::label::NewLabel::default())
             .add_startup_systems((setup, settings::label::init_new_label))
             .add_systems((
                 task::handle_load_folder_task,
                 control_bar.before(handle_requests),
                 shortcut::handle_shortcuts.before(handle_requests),
+                shortcut::handle_gamepad_shortcuts,
                 menu_bar.before(handle_requests),
                 settings::label::window.after(menu_bar).after(control_bar),
                 settings::general::window.after(menu_bar).after(control_bar),
+                settings::keys::window
+                    .after(menu_bar)
+                    .after(control_bar)
+                    .before(shortcut::handle_shortcuts),
+                bookmarks::window.after(menu_bar).after(control_bar),
+                camera_path::window.after(menu_bar).after(control_bar),
+                annotations::panel.after(menu_bar).after(control_bar),
+                validation::window.after(menu_bar).after(control_bar),
                 Inspector::detect_point_under_curser.before(Inspector::draw),
                 Inspector::draw.after(menu_bar).after(control_bar),
                 handle_requests,
-            ));
+            ))
+            // a system tuple holds at most 15 systems
+            .add_system(notifications::window.after(menu_bar).after(control_bar));
     }
 }
 
@@ -55,18 +68,31 @@ fn setup(mut egui_context: EguiContexts) {
 pub struct UiState {
     pub folder_dialog: DialogRequest,
     pub label_folder_dialog: DialogRequest,
+    pub comparison_folder_dialog: DialogRequest,
+    pub comparison_label_folder_dialog: DialogRequest,
     pub fullscreen: ToggleRequest,
     pub color_settings_visible: bool,
     pub general_settings_visible: bool,
+    pub bookmarks_visible: bool,
+    pub camera_path_visible: bool,
+    pub annotations_visible: bool,
+    pub validation_visible: bool,
+    pub notifications_visible: bool,
+    pub key_bindings_visible: bool,
+    /// The key binding window waits for a key, shortcuts are ignored meanwhile.
+    pub capturing_key: bool,
 }
 fn menu_bar(
     mut egui_context: EguiContexts,
     mut ui_state: ResMut<UiState>,
     mut player_state: ResMut<lidar::PlayerState>,
     mut inspector: ResMut<Inspector>,
+    mut config: ResMut<PlayerConfig>,
     mut exit: EventWriter<AppExit>,
+    mut camera_events: EventWriter<CameraEvent>,
 ) {
     let ctx = egui_context.ctx_mut();
+    let bindings = config.persistent.key_bindings.clone();
     let frame = egui::Frame {
         fill: Color32::from_rgba_premultiplied(10, 10, 10, 200),
         inner_margin: Margin {
@@ -87,7 +113,7 @@ fn menu_bar(
                         .add_enabled(
                             !ui_state.folder_dialog.is_open(),
                             egui::Button::new("Open Sequence Folder...")
-                                .shortcut_text("O")
+                                .shortcut_text(bindings.label(Action::OpenSequence))
                                 .wrap(false),
                         )
                         .clicked()
@@ -95,6 +121,13 @@ fn menu_bar(
                         ui_state.folder_dialog.request();
                         ui.close_menu();
                     }
+                    if ui
+                        .add(egui::Button::new("Validate Sequence...").wrap(false))
+                        .clicked()
+                    {
+                        ui_state.validation_visible = !ui_state.validation_visible;
+                        ui.close_menu();
+                    }
                     if ui
                         .add(egui::Button::new("General-Settings").wrap(false))
                         .clicked()
@@ -102,6 +135,13 @@ fn menu_bar(
                         ui_state.general_settings_visible = !ui_state.general_settings_visible;
                         ui.close_menu();
                     }
+                    if ui
+                        .add(egui::Button::new("Key-Bindings").wrap(false))
+                        .clicked()
+                    {
+                        ui_state.key_bindings_visible = !ui_state.key_bindings_visible;
+                        ui.close_menu();
+                    }
                     ui.separator();
                     if ui.button("Exit").clicked() {
                         exit.send(AppExit);
@@ -112,7 +152,7 @@ fn menu_bar(
                     if ui
                         .add(
                             egui::Button::new("Fullscreen")
-                                .shortcut_text("F")
+                                .shortcut_text(bindings.label(Action::Fullscreen))
                                 .wrap(false),
                         )
                         .clicked()
@@ -123,7 +163,7 @@ fn menu_bar(
                     if ui
                         .add(
                             egui::Button::new("Inspector")
-                                .shortcut_text("I")
+                                .shortcut_text(bindings.label(Action::Inspector))
                                 .wrap(false),
                         )
                         .clicked()
@@ -131,6 +171,102 @@ fn menu_bar(
                         inspector.visible = !inspector.visible;
                         ui.close_menu();
                     }
+                    if ui
+                        .add(egui::Button::new("Camera-Bookmarks").wrap(false))
+                        .clicked()
+                    {
+                        ui_state.bookmarks_visible = !ui_state.bookmarks_visible;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add(egui::Button::new("Camera-Path").wrap(false))
+                        .clicked()
+                    {
+                        ui_state.camera_path_visible = !ui_state.camera_path_visible;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add(egui::Button::new("Annotations").wrap(false))
+                        .clicked()
+                    {
+                        ui_state.annotations_visible = !ui_state.annotations_visible;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add(egui::Button::new("Notifications").wrap(false))
+                        .clicked()
+                    {
+                        ui_state.notifications_visible = !ui_state.notifications_visible;
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Camera", |ui| {
+                        let mut camera_mode = config.persistent.camera_mode;
+                        for mode in CameraMode::ALL {
+                            ui.radio_value(&mut camera_mode, mode, mode.name());
+                        }
+                        ui.label(
+                            RichText::new(format!(
+                                "Switch with {}",
+                                bindings.label(Action::SwitchCameraMode)
+                            ))
+                            .weak(),
+                        );
+                        if camera_mode != config.persistent.camera_mode {
+                            config.persistent.camera_mode = camera_mode;
+                            config.save();
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        if ui
+                            .add(
+                                egui::Button::new("Frame All")
+                                    .shortcut_text(bindings.label(Action::FrameAll))
+                                    .wrap(false),
+                            )
+                            .clicked()
+                        {
+                            camera_events.send(CameraEvent::FrameAll);
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add(
+                                egui::Button::new("Reset Camera")
+                                    .shortcut_text(bindings.label(Action::ResetCamera))
+                                    .wrap(false),
+                            )
+                            .clicked()
+                        {
+                            camera_events.send(CameraEvent::Reset);
+                            ui.close_menu();
+                        }
+                    });
+                    ui.menu_button("Viewports", |ui| {
+                        let mut layout = config.persistent.viewport_layout;
+                        for option in ViewportLayout::ALL {
+                            ui.radio_value(&mut layout, option, option.name());
+                        }
+                        if layout != config.persistent.viewport_layout {
+                            config.persistent.viewport_layout = layout;
+                            config.save();
+                            ui.close_menu();
+                        }
+                        if layout.count() > 1 {
+                            ui.separator();
+                        }
+                        for index in 1..layout.count() {
+                            ui.menu_button(format!("Viewport {}", index + 1), |ui| {
+                                let mut camera_mode = config.persistent.camera_mode_of(index);
+                                for mode in CameraMode::ALL {
+                                    ui.radio_value(&mut camera_mode, mode, mode.name());
+                                }
+                                if camera_mode != config.persistent.camera_mode_of(index) {
+                                    config.persistent.set_camera_mode(index, camera_mode);
+                                    config.save();
+                                    ui.close_menu();
+                                }
+                            });
+                        }
+                    });
                 });
                 ui.menu_button("Playback", |ui| {
                     if ui
@@ -140,7 +276,7 @@ fn menu_bar(
                             } else {
                                 "Pause"
                             })
-                            .shortcut_text("Space")
+                            .shortcut_text(bindings.label(Action::PlayPause))
                             .wrap(false),
                         )
                         .clicked()
@@ -154,7 +290,7 @@ fn menu_bar(
                         .add_enabled(
                             !ui_state.label_folder_dialog.is_open(),
                             egui::Button::new("Open Label Folder...")
-                                .shortcut_text("L")
+                                .shortcut_text(bindings.label(Action::OpenLabels))
                                 .wrap(false),
                         )
                         .clicked()
@@ -165,10 +301,7 @@ fn menu_bar(
                     if ui
                         .add(
                             egui::Button::new("Discard")
-                                .shortcut_text(ctx.format_shortcut(&KeyboardShortcut {
-                                    key: Key::L,
-                                    modifiers: Modifiers::SHIFT,
-                                }))
+                                .shortcut_text(bindings.label(Action::DiscardLabels))
                                 .wrap(false),
                         )
                         .clicked()
@@ -184,6 +317,44 @@ fn menu_bar(
                         ui_state.color_settings_visible = !ui_state.color_settings_visible;
                         ui.close_menu();
                     }
+                });
+                ui.menu_button("Compare", |ui| {
+                    // the comparison is shown next to the opened sequence
+                    let sequence_opened = player_state.get_sequence_folder().is_some();
+                    if ui
+                        .add_enabled(
+                            sequence_opened && !ui_state.comparison_folder_dialog.is_open(),
+                            egui::Button::new("Open Sequence Folder...").wrap(false),
+                        )
+                        .on_hover_text("Shows a second sequence next to the opened one")
+                        .on_disabled_hover_text("Open a sequence first")
+                        .clicked()
+                    {
+                        ui_state.comparison_folder_dialog.request();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            sequence_opened && !ui_state.comparison_label_folder_dialog.is_open(),
+                            egui::Button::new("Open Label Folder...").wrap(false),
+                        )
+                        .on_hover_text("Shows the opened sequence a second time with other labels")
+                        .on_disabled_hover_text("Open a sequence first")
+                        .clicked()
+                    {
+                        ui_state.comparison_label_folder_dialog.request();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            player_state.is_comparing(),
+                            egui::Button::new("Close").wrap(false),
+                        )
+                        .clicked()
+                    {
+                        player_state.close_comparison();
+                        ui.close_menu();
+                    }
                 })
             });
         });
@@ -200,6 +371,8 @@ fn control_bar(
     images: Local<UiHandles>,
     mut control_bar_state: Local<ControlBarState>,
     mut ui_state: ResMut<UiState>,
+    mut config: ResMut<PlayerConfig>,
+    (annotations, heatmap): (Res<TimelineAnnotations>, Res<LabelHeatmap>),
 ) {
     let frame = egui::Frame {
         fill: Color32::from_rgba_premultiplied(10, 10, 10, 200),
@@ -227,11 +400,47 @@ fn control_bar(
                 let padding = 10.0;
                 ui.add_space(padding);
                 ui.spacing_mut().slider_width = ui.available_width() - padding;
+                let rail_segments = player
+                    .get_load_segments()
+                    .into_iter()
+                    .filter_map(|(frames, load_state)| {
+                        let color = match load_state {
+                            LoadState::Loaded => Color32::from_rgb(111, 111, 111),
+                            LoadState::Requested => Color32::from_rgb(80, 80, 110),
+                            LoadState::Failed => Color32::from_rgb(200, 40, 40),
+                            LoadState::NotRequested => return None,
+                        };
+                        Some(RailSegment {
+                            range: *frames.start() as f64
+                                ..=(*frames.end() + 1).min(max_frame) as f64,
+                            color,
+                        })
+                    })
+                    .collect();
+                let sensor_fps = player.get_sensor_fps();
                 let slider_response = ui.add(
                     VideoSlider::new(&mut frame, 0..=max_frame)
-                        .buffer_value(&player.get_buffer_frame())
-                        .buffer_hint_color(Color32::from_rgb(111, 111, 111))
+                        .rail_segments(rail_segments)
+                        .ticks(
+                            annotations
+                                .annotations
+                                .iter()
+                                .filter_map(|annotation| annotation.frame)
+                                .filter(|frame| *frame <= max_frame)
+                                .collect(),
+                        )
+                        .tick_color(Color32::from_rgb(100, 180, 255))
+                        .tooltip_formatter(|frame| {
+                            let time = format!("{frame} | {:.1}s", frame / sensor_fps);
+                            // a pixel of the rail covers several frames on long sequences
+                            let tolerance = max_frame as f64 / 300.0;
+                            match annotations.near(frame, tolerance) {
+                                Some(annotation) => format!("{time} | {}", annotation.text),
+                                None => time,
+                            }
+                        })
                         .slider_color(Color32::from_rgb(250, 11, 11))
+                        .repeat_markers(player.get_repeat_markers())
                         .show_value(false),
                 );
                 if slider_response.drag_started() {
@@ -248,7 +457,57 @@ fn control_bar(
                 if slider_response.changed() {
                     player.request_frame(frame);
                 }
+                let clicked_frame = VideoSlider::secondary_clicked_value(&slider_response)
+                    .map_or(frame, |value| value as usize);
+                slider_response.context_menu(|ui| {
+                    if ui
+                        .button(format!("Set A at Frame {clicked_frame}"))
+                        .clicked()
+                    {
+                        player.set_repeat_start(clicked_frame);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button(format!("Set B at Frame {clicked_frame}"))
+                        .clicked()
+                    {
+                        player.set_repeat_end(clicked_frame);
+                        ui.close_menu();
+                    }
+                    if ui.button("Clear A-B").clicked() {
+                        player.clear_repeat();
+                        ui.close_menu();
+                    }
+                });
             });
+            if let Some(progress) = heatmap.progress() {
+                ui.horizontal(|ui| {
+                    ui.add_space(10.0);
+                    ui.label(
+                        RichText::new(format!("Counting labels {:.0}%", progress * 100.0))
+                            .color(Color32::GRAY)
+                            .small(),
+                    );
+                });
+            } else if !heatmap.counts.is_empty() {
+                ui.horizontal(|ui| {
+                    let padding = 10.0;
+                    ui.add_space(padding);
+                    ui.spacing_mut().slider_width = ui.available_width() - padding;
+                    let strip_response = ui.add(HeatStrip::new(&heatmap.counts, max_frame));
+                    let rect = strip_response.rect;
+                    if let Some(position) = strip_response.interact_pointer_pos() {
+                        player.request_frame(HeatStrip::frame_at(&rect, max_frame, position.x));
+                    }
+                    if let Some(position) = strip_response.hover_pos() {
+                        let hovered_frame = HeatStrip::frame_at(&rect, max_frame, position.x);
+                        let count = heatmap.counts.get(hovered_frame).copied().unwrap_or(0);
+                        strip_response.on_hover_text_at_pointer(format!(
+                            "Frame {hovered_frame}: {count} points of the heatmap classes"
+                        ));
+                    }
+                });
+            }
             ui.horizontal(|ui| {
                 let button_size = Vec2::new(20.0, 20.0);
                 ui.add_space(15.0);
@@ -256,7 +515,6 @@ fn control_bar(
                     .add(egui::ImageButton::new(previous_frame_button, button_size).frame(false))
                     .on_hover_text("Previous Frame")
                     .clicked()
-                    || ui.input(|input| input.key_pressed(Key::ArrowLeft))
                 {
                     player.previous_frame();
                 }
@@ -283,13 +541,62 @@ fn control_bar(
                     ),
                 )
                 .on_hover_text("Frame Count");
-                let padding = 80.0;
+                if let Some(frame_id) = player.get_frame_id(frame) {
+                    ui.label(RichText::new(frame_id).weak())
+                        .on_hover_text("Frame Id, the file name of the frame");
+                }
+                let playback_mode = player.get_playback_mode();
+                if ui
+                    .button(playback_mode.name())
+                    .on_hover_text("Playback Mode")
+                    .clicked()
+                {
+                    player.set_playback_mode(playback_mode.next());
+                    config.persistent.playback_mode = playback_mode.next();
+                    config.save();
+                }
+                let (repeat_start, repeat_end) = player.get_repeat_markers();
+                if ui
+                    .selectable_label(repeat_start.is_some(), "A")
+                    .on_hover_text("Repeat from the current frame")
+                    .clicked()
+                {
+                    let frame = player.get_frame();
+                    player.set_repeat_start(frame);
+                }
+                if ui
+                    .selectable_label(repeat_end.is_some(), "B")
+                    .on_hover_text("Repeat up to the current frame")
+                    .clicked()
+                {
+                    let frame = player.get_frame();
+                    player.set_repeat_end(frame);
+                }
+                if (repeat_start.is_some() || repeat_end.is_some())
+                    && ui.button("✖").on_hover_text("Clear A-B").clicked()
+                {
+                    player.clear_repeat();
+                }
+                let padding = 200.0;
                 ui.add_space(ui.available_width() - padding);
+                ui.add_sized(
+                    bevy_egui::egui::Vec2::new(110.0, 20.0),
+                    egui::Label::new(
+                        RichText::new(format!(
+                            "{} / {} MB",
+                            player.get_memory_usage() / lidar::PlayerState::MEGABYTE,
+                            player.get_memory_budget() / lidar::PlayerState::MEGABYTE
+                        ))
+                        .color(Color32::GRAY)
+                        .text_style(egui::TextStyle::Button),
+                    ),
+                )
+                .on_hover_text("Frame Cache");
                 let mut time_scale = player.get_time_scale();
                 if ui
                     .add(
                         egui::widgets::DragValue::new(&mut time_scale)
-                            .clamp_range(0.0..=1000.0)
+                            .clamp_range(-1000.0..=1000.0)
                             .speed(0.1)
                             .suffix("x"),
                     )
@@ -333,4 +640,10 @@ fn handle_requests(
     ui_state.label_folder_dialog.on_request(|| {
         task::spawn_load_folder_task(&mut commands, task::FolderTaskType::Label);
     });
+    ui_state.comparison_folder_dialog.on_request(|| {
+        task::spawn_load_folder_task(&mut commands, task::FolderTaskType::ComparisonSequence);
+    });
+    ui_state.comparison_label_folder_dialog.on_request(|| {
+        task::spawn_load_folder_task(&mut commands, task::FolderTaskType::ComparisonLabel);
+    });
 }
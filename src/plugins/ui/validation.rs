@@ -0,0 +1,100 @@
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use bevy_egui::{egui::*, *};
+use futures_lite::future;
+
+use super::ui_plugin::UiState;
+use crate::{
+    plugins::lidar::PlayerState,
+    validation::{validate_sequence, ValidationReport},
+};
+
+#[derive(Resource, Default)]
+pub struct SequenceValidation {
+    task: Option<Task<ValidationReport>>,
+    report: Option<ValidationReport>,
+}
+
+pub fn window(
+    mut egui_context: EguiContexts,
+    mut ui_state: ResMut<UiState>,
+    player: Res<PlayerState>,
+    mut validation: ResMut<SequenceValidation>,
+) {
+    if let Some(task) = &mut validation.task {
+        if let Some(report) = future::block_on(future::poll_once(task)) {
+            validation.report = Some(report);
+            validation.task = None;
+        }
+    }
+    let ctx = egui_context.ctx_mut();
+    egui::Window::new("Sequence-Validation")
+        .open(&mut ui_state.validation_visible)
+        .resizable(true)
+        .show(ctx, |ui| {
+            let folder = player.get_sequence_folder();
+            match folder {
+                Some(folder) => ui.label(RichText::new(folder.to_string_lossy()).weak()),
+                None => ui.label("No sequence is opened."),
+            };
+            ui.horizontal(|ui| {
+                let running = validation.task.is_some();
+                if ui
+                    .add_enabled(folder.is_some() && !running, egui::Button::new("Validate"))
+                    .on_hover_text("Checks numbering, sizes and coordinates of all frame files")
+                    .clicked()
+                {
+                    if let Some(folder) = folder {
+                        let folder = folder.to_path_buf();
+                        validation.report = None;
+                        validation.task = Some(
+                            AsyncComputeTaskPool::get()
+                                .spawn(async move { validate_sequence(&folder) }),
+                        );
+                    }
+                }
+                if running {
+                    ui.spinner();
+                }
+            });
+            let Some(report) = &validation.report else {
+                return;
+            };
+            ui.separator();
+            if report.issues.is_empty() {
+                ui.label(
+                    RichText::new(format!("{} frames, no issues found.", report.frame_count))
+                        .color(Color32::from_rgb(100, 200, 100)),
+                );
+                return;
+            }
+            let warning_color = Color32::from_rgb(230, 180, 60);
+            let summary_color = match report.is_valid() {
+                true => warning_color,
+                false => Color32::from_rgb(230, 90, 90),
+            };
+            ui.label(
+                RichText::new(format!(
+                    "{} frames, {} issues and {} warnings found:",
+                    report.frame_count,
+                    report.error_count(),
+                    report.warning_count()
+                ))
+                .color(summary_color),
+            );
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for issue in &report.issues {
+                        match issue.is_warning() {
+                            true => {
+                                ui.label(RichText::new(format!("⚠ {issue}")).color(warning_color))
+                            }
+                            false => ui.label(issue.to_string()),
+                        };
+                    }
+                });
+        });
+}
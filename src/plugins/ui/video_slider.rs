@@ -6,14 +6,21 @@
 use bevy_egui::egui::{
     egui_assert,
     emath::{self, *},
-    epaint, Color32, DragValue, FontId, Key, Label, Response, RichText, Sense, SliderOrientation,
-    TextStyle, Ui, Widget, WidgetInfo,
+    epaint, Color32, DragValue, FontId, Label, Response, RichText, Sense, SliderOrientation,
+    Stroke, TextStyle, Ui, Widget, WidgetInfo,
 };
 use std::{f64::INFINITY, ops::RangeInclusive};
 
 // ----------------------------------------------------------------------------
 
 type NumFormatter<'a> = Box<dyn 'a + Fn(f64, RangeInclusive<usize>) -> String>;
+type TooltipFormatter<'a> = Box<dyn 'a + Fn(f64) -> String>;
+
+/// Part of the rail painted in its own color, e.g. the loaded frames of a video.
+pub struct RailSegment {
+    pub range: RangeInclusive<f64>,
+    pub color: Color32,
+}
 
 // ----------------------------------------------------------------------------
 
@@ -68,7 +75,9 @@ struct SliderSpec {
 #[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
 pub struct VideoSlider<'a> {
     get_set_value: GetSetValue<'a>,
-    buffer_value: f64,
+    rail_segments: Vec<RailSegment>,
+    ticks: Vec<f64>,
+    tick_color: Option<Color32>,
     range: RangeInclusive<f64>,
     spec: SliderSpec,
     clamp_to_range: bool,
@@ -80,7 +89,8 @@ pub struct VideoSlider<'a> {
     text: String,
     text_color: Option<Color32>,
     slider_color: Option<Color32>,
-    buffer_hint_color: Option<Color32>,
+    tooltip_formatter: Option<TooltipFormatter<'a>>,
+    repeat_markers: (Option<f64>, Option<f64>),
     /// Sets the minimal step of the widget value
     step: Option<f64>,
     min_decimals: usize,
@@ -108,10 +118,12 @@ impl<'a> VideoSlider<'a> {
 
     pub fn from_get_set(
         range: RangeInclusive<f64>,
-        mut get_set_value: impl 'a + FnMut(Option<f64>) -> f64,
+        get_set_value: impl 'a + FnMut(Option<f64>) -> f64,
     ) -> Self {
         Self {
-            buffer_value: get_set_value(None),
+            rail_segments: Vec::new(),
+            ticks: Vec::new(),
+            tick_color: None,
             get_set_value: Box::new(get_set_value),
             range,
             spec: SliderSpec {
@@ -128,7 +140,8 @@ impl<'a> VideoSlider<'a> {
             text: Default::default(),
             text_color: None,
             slider_color: None,
-            buffer_hint_color: None,
+            tooltip_formatter: None,
+            repeat_markers: (None, None),
             step: None,
             min_decimals: 0,
             max_decimals: None,
@@ -171,13 +184,38 @@ impl<'a> VideoSlider<'a> {
         self
     }
 
-    pub fn buffer_hint_color(mut self, buffer_hint_color: Color32) -> Self {
-        self.buffer_hint_color = Some(buffer_hint_color);
+    /// Segments painted onto the rail below the slider, e.g. the buffered parts of a video.
+    pub fn rail_segments(mut self, rail_segments: Vec<RailSegment>) -> Self {
+        self.rail_segments = rail_segments;
+        self
+    }
+
+    /// Values marked with a short line across the rail.
+    pub fn ticks<Num: emath::Numeric>(mut self, ticks: Vec<Num>) -> Self {
+        self.ticks = ticks.into_iter().map(|tick| tick.to_f64()).collect();
         self
     }
 
-    pub fn buffer_value<Num: emath::Numeric>(mut self, value: &Num) -> Self {
-        self.buffer_value = value.to_f64();
+    pub fn tick_color(mut self, tick_color: Color32) -> Self {
+        self.tick_color = Some(tick_color);
+        self
+    }
+
+    /// Text shown above the pointer while hovering or dragging, the value under the pointer is passed.
+    pub fn tooltip_formatter(mut self, formatter: impl 'a + Fn(f64) -> String) -> Self {
+        self.tooltip_formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Marks the start (A) and end (B) of a repeated range, the range between them is highlighted.
+    pub fn repeat_markers<Num: emath::Numeric>(
+        mut self,
+        markers: (Option<Num>, Option<Num>),
+    ) -> Self {
+        self.repeat_markers = (
+            markers.0.map(|start| start.to_f64()),
+            markers.1.map(|end| end.to_f64()),
+        );
         self
     }
 
@@ -333,6 +371,17 @@ impl<'a> VideoSlider<'a> {
         self.range.clone()
     }
 
+    fn round_value(&self, value: f64) -> f64 {
+        self.max_decimals.map_or(value, |max_decimals| {
+            emath::round_to_decimals(value, max_decimals)
+        })
+    }
+
+    /// Value under the pointer when the slider was last right-clicked, e.g. for its context menu.
+    pub fn secondary_clicked_value(response: &Response) -> Option<f64> {
+        response.ctx.data_mut(|data| data.get_temp(response.id))
+    }
+
     /// For instance, `position` is the mouse position and `position_range` is the physical location of the slider on the screen.
     fn value_from_position(&self, position: f32, position_range: RangeInclusive<f32>) -> f64 {
         let normalized = remap_clamp(position, position_range, 0.0..=1.0) as f64;
@@ -363,7 +412,14 @@ impl<'a> VideoSlider<'a> {
         let rect = &response.rect;
         let position_range = self.position_range(rect);
 
-        if let Some(pointer_position_2d) = response.interact_pointer_pos() {
+        let secondary = response.secondary_clicked() || ui.input(|i| i.pointer.secondary_down());
+        if let (true, Some(pointer_position_2d)) = (secondary, response.interact_pointer_pos()) {
+            // remember where the context menu was opened instead of seeking there
+            let position = self.pointer_position(pointer_position_2d);
+            let value =
+                self.round_value(self.value_from_position(position, position_range.clone()));
+            ui.data_mut(|data| data.insert_temp(response.id, value));
+        } else if let Some(pointer_position_2d) = response.interact_pointer_pos() {
             let position = self.pointer_position(pointer_position_2d);
             let new_value = if self.smart_aim {
                 let aim_radius = ui.input(|input| input.aim_radius());
@@ -389,15 +445,44 @@ impl<'a> VideoSlider<'a> {
                 fill: Color32::from_rgb(60, 60, 60),
                 stroke: Default::default(),
             });
-            let position_buffer =
-                self.position_from_value(self.buffer_value, position_range.clone());
-            let buffer_rect = self.slider_rect(position_buffer, &rail_rect);
-            ui.painter().add(epaint::RectShape {
-                rect: buffer_rect,
-                rounding: ui.visuals().widgets.inactive.rounding,
-                fill: self.buffer_hint_color.unwrap_or(Color32::WHITE),
-                stroke: Default::default(),
-            });
+            for segment in &self.rail_segments {
+                let start =
+                    self.position_from_value(*segment.range.start(), position_range.clone());
+                let end = self.position_from_value(*segment.range.end(), position_range.clone());
+                let mut segment_rect = rail_rect;
+                match self.orientation {
+                    SliderOrientation::Horizontal => {
+                        segment_rect.set_left(start);
+                        // single frames stay visible on long sequences
+                        segment_rect.set_right(end.max(start + 1.0));
+                    }
+                    SliderOrientation::Vertical => {
+                        segment_rect.set_bottom(start);
+                        segment_rect.set_top(end.min(start - 1.0));
+                    }
+                }
+                ui.painter().add(epaint::RectShape {
+                    rect: segment_rect,
+                    rounding: Default::default(),
+                    fill: segment.color,
+                    stroke: Default::default(),
+                });
+            }
+            let tick_stroke = Stroke::new(2.0, self.tick_color.unwrap_or(Color32::WHITE));
+            for tick in &self.ticks {
+                let position = self.position_from_value(*tick, position_range.clone());
+                let center = self.marker_center(position, &rail_rect);
+                let points = match self.orientation {
+                    SliderOrientation::Horizontal => {
+                        [center - vec2(0.0, 5.0), center + vec2(0.0, 5.0)]
+                    }
+                    SliderOrientation::Vertical => {
+                        [center - vec2(5.0, 0.0), center + vec2(5.0, 0.0)]
+                    }
+                };
+                ui.painter().line_segment(points, tick_stroke);
+            }
+            self.paint_repeat_markers(ui, rect, &rail_rect, position_range.clone());
             let slider_rect = self.slider_rect(position_1d, &rail_rect);
             ui.painter().add(epaint::RectShape {
                 rect: slider_rect,
@@ -405,12 +490,24 @@ impl<'a> VideoSlider<'a> {
                 fill: self.slider_color.unwrap_or(Color32::WHITE),
                 stroke: Default::default(),
             });
-            if response.dragged() {
-                let tooltip_rect = rail_rect;
+            let tooltip = match (response.dragged(), response.hover_pos()) {
+                (true, _) => Some((position_1d, value)),
+                (false, Some(hover_position)) => {
+                    let position = self.pointer_position(hover_position);
+                    let hovered_value = self.value_from_position(position, position_range.clone());
+                    Some((position, self.round_value(hovered_value)))
+                }
+                _ => None,
+            };
+            if let Some((position, tooltip_value)) = tooltip {
+                let text = match &self.tooltip_formatter {
+                    Some(formatter) => formatter(tooltip_value),
+                    None => tooltip_value.to_string(),
+                };
                 ui.painter().text(
-                    Pos2::new(position_1d, tooltip_rect.top() - 20.0),
+                    Pos2::new(position, rail_rect.top() - 20.0),
                     Align2::CENTER_CENTER,
-                    value.to_string(),
+                    text,
                     FontId::proportional(20.0),
                     Color32::WHITE,
                 );
@@ -425,6 +522,57 @@ impl<'a> VideoSlider<'a> {
         }
     }
 
+    fn paint_repeat_markers(
+        &self,
+        ui: &Ui,
+        rect: &Rect,
+        rail_rect: &Rect,
+        position_range: RangeInclusive<f32>,
+    ) {
+        let (start, end) = self.repeat_markers;
+        if start.is_none() && end.is_none() {
+            return;
+        }
+        let marker_color = Color32::from_rgb(255, 200, 0);
+        let start_position = self.position_from_value(
+            start.unwrap_or(*self.range().start()),
+            position_range.clone(),
+        );
+        let end_position =
+            self.position_from_value(end.unwrap_or(*self.range().end()), position_range);
+        let mut range_rect = *rail_rect;
+        match self.orientation {
+            SliderOrientation::Horizontal => {
+                range_rect.set_left(start_position);
+                range_rect.set_right(end_position);
+            }
+            SliderOrientation::Vertical => {
+                range_rect.set_bottom(start_position);
+                range_rect.set_top(end_position);
+            }
+        }
+        ui.painter().add(epaint::RectShape {
+            rect: range_rect.expand(1.0),
+            rounding: ui.visuals().widgets.inactive.rounding,
+            fill: marker_color.linear_multiply(0.4),
+            stroke: Default::default(),
+        });
+        let markers = [(start, start_position), (end, end_position)];
+        for (_, position) in markers.iter().filter(|(marker, _)| marker.is_some()) {
+            let center = self.marker_center(*position, rail_rect);
+            let points = match self.orientation {
+                SliderOrientation::Horizontal => {
+                    [pos2(center.x, rect.top()), pos2(center.x, rect.bottom())]
+                }
+                SliderOrientation::Vertical => {
+                    [pos2(rect.left(), center.y), pos2(rect.right(), center.y)]
+                }
+            };
+            ui.painter()
+                .line_segment(points, Stroke::new(2.0, marker_color));
+        }
+    }
+
     fn slider_rect(&self, position_1d: f32, rail_rect: &Rect) -> Rect {
         let slider_pos = self.marker_center(position_1d, rail_rect);
         let mut rect = rail_rect.clone();
@@ -478,15 +626,10 @@ impl<'a> VideoSlider<'a> {
     }
 
     fn value_ui(&mut self, ui: &mut Ui, position_range: RangeInclusive<f32>) -> Response {
-        // If [`DragValue`] is controlled from the keyboard and `step` is defined, set speed to `step`
-        let change = ui.input(|input| {
-            input.num_presses(Key::ArrowUp) as i32 + input.num_presses(Key::ArrowRight) as i32
-                - input.num_presses(Key::ArrowDown) as i32
-                - input.num_presses(Key::ArrowLeft) as i32
-        });
+        // the arrow keys belong to the remappable shortcuts, a defined `step` is used for dragging too
         let speed = match self.step {
-            Some(step) if change != 0 => step,
-            _ => self.current_gradient(&position_range),
+            Some(step) => step,
+            None => self.current_gradient(&position_range),
         };
         let mut value = self.get_value();
         let response = ui.add({
@@ -0,0 +1,504 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::io;
+
+/// Bytes of a point: x, y, z and remission as f32.
+const POINT_SIZE: u64 = 16;
+/// Bytes of a label: class and instance id as u16.
+const LABEL_SIZE: u64 = 4;
+
+pub enum ValidationIssue {
+    MissingFolder(PathBuf),
+    ReadFolder(PathBuf, String),
+    NonNumericName(PathBuf),
+    MissingFrames {
+        folder: PathBuf,
+        first: u64,
+        last: u64,
+    },
+    InvalidFileSize {
+        path: PathBuf,
+        size: u64,
+        record_size: u64,
+    },
+    ReadFile(PathBuf, String),
+    MissingLabelFile(String),
+    LabelWithoutPoints(String),
+    CountMismatch {
+        frame: String,
+        points: u64,
+        labels: u64,
+    },
+    NonFiniteCoordinates {
+        frame: String,
+        count: usize,
+    },
+}
+
+impl ValidationIssue {
+    /// Warnings point out unusual but playable sequences, like gaps in the numbering.
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            ValidationIssue::NonNumericName(_) | ValidationIssue::MissingFrames { .. }
+        )
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::MissingFolder(path) => write!(formatter, "Missing folder {path:?}"),
+            ValidationIssue::ReadFolder(path, error) => {
+                write!(formatter, "Cannot read folder {path:?}: {error}")
+            }
+            ValidationIssue::NonNumericName(path) => write!(
+                formatter,
+                "File name is neither a frame number nor a timestamp, it is played after the numbered frames: {path:?}"
+            ),
+            ValidationIssue::MissingFrames {
+                folder,
+                first,
+                last,
+            } if first == last => {
+                write!(formatter, "Frame {first} is missing in {folder:?}")
+            }
+            ValidationIssue::MissingFrames {
+                folder,
+                first,
+                last,
+            } => {
+                write!(
+                    formatter,
+                    "Frames {first} to {last} are missing in {folder:?}"
+                )
+            }
+            ValidationIssue::InvalidFileSize {
+                path,
+                size,
+                record_size,
+            } => write!(
+                formatter,
+                "Size of {path:?} ({size} bytes) is not a multiple of {record_size} bytes"
+            ),
+            ValidationIssue::ReadFile(path, error) => {
+                write!(formatter, "Cannot read file {path:?}: {error}")
+            }
+            ValidationIssue::MissingLabelFile(frame) => {
+                write!(formatter, "Frame {frame} has no label file")
+            }
+            ValidationIssue::LabelWithoutPoints(frame) => {
+                write!(formatter, "Frame {frame} has labels but no points")
+            }
+            ValidationIssue::CountMismatch {
+                frame,
+                points,
+                labels,
+            } => write!(
+                formatter,
+                "Frame {frame} has {points} points but {labels} labels"
+            ),
+            ValidationIssue::NonFiniteCoordinates { frame, count } => {
+                write!(
+                    formatter,
+                    "Frame {frame} has {count} points with NaN or infinite coordinates"
+                )
+            }
+        }
+    }
+}
+
+pub struct ValidationReport {
+    pub folder: PathBuf,
+    pub frame_count: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// A sequence with only warnings is valid.
+    pub fn is_valid(&self) -> bool {
+        self.issues.iter().all(ValidationIssue::is_warning)
+    }
+    pub fn warning_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.is_warning())
+            .count()
+    }
+    pub fn error_count(&self) -> usize {
+        self.issues.len() - self.warning_count()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            formatter,
+            "Sequence {:?} with {} frames",
+            self.folder, self.frame_count
+        )?;
+        if self.issues.is_empty() {
+            return writeln!(formatter, "No issues found.");
+        }
+        writeln!(
+            formatter,
+            "{} issues and {} warnings found:",
+            self.error_count(),
+            self.warning_count()
+        )?;
+        for issue in &self.issues {
+            match issue.is_warning() {
+                true => writeln!(formatter, "- Warning: {issue}")?,
+                false => writeln!(formatter, "- {issue}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the files of a sequence without loading it, see the readme for the expected structure.
+/// Frames are matched by their file stem like the viewer does.
+pub fn validate_sequence(dir_path: &Path) -> ValidationReport {
+    let mut issues = Vec::new();
+    let point_folder = io::point_folder(dir_path);
+    let label_folder = io::label_folder(dir_path);
+    let frame_ids = folder_frame_ids(&point_folder, "bin", &mut issues);
+    let label_ids = match label_folder.is_dir() {
+        true => Some(
+            folder_frame_ids(&label_folder, "label", &mut issues)
+                .into_iter()
+                .collect::<HashSet<_>>(),
+        ),
+        false => None,
+    };
+    report_missing_frames(&point_folder, &frame_ids, &mut issues);
+    for frame_id in &frame_ids {
+        let point_path = point_folder.join(format!("{frame_id}.bin"));
+        if frame_id.parse::<f64>().is_err() {
+            issues.push(ValidationIssue::NonNumericName(point_path.clone()));
+        }
+        let point_count = check_point_file(frame_id, &point_path, &mut issues);
+        let Some(label_ids) = &label_ids else {
+            continue;
+        };
+        if !label_ids.contains(frame_id) {
+            issues.push(ValidationIssue::MissingLabelFile(frame_id.clone()));
+            continue;
+        }
+        let label_path = label_folder.join(format!("{frame_id}.label"));
+        let label_count = record_count(&label_path, LABEL_SIZE, &mut issues);
+        if let (Some(points), Some(labels)) = (point_count, label_count) {
+            if points != labels {
+                issues.push(ValidationIssue::CountMismatch {
+                    frame: frame_id.clone(),
+                    points,
+                    labels,
+                });
+            }
+        }
+    }
+    if let Some(label_ids) = label_ids {
+        let point_ids: HashSet<_> = frame_ids.iter().collect();
+        let mut orphans: Vec<_> = label_ids
+            .into_iter()
+            .filter(|frame_id| !point_ids.contains(frame_id))
+            .collect();
+        orphans.sort_by(|a, b| io::compare_frame_ids(a, b));
+        issues.extend(orphans.into_iter().map(ValidationIssue::LabelWithoutPoints));
+    }
+    ValidationReport {
+        folder: dir_path.to_path_buf(),
+        frame_count: frame_ids.len(),
+        issues,
+    }
+}
+
+/// File stems of the files with the extension in the order the viewer plays them.
+fn folder_frame_ids(
+    folder: &Path,
+    extension: &str,
+    issues: &mut Vec<ValidationIssue>,
+) -> Vec<String> {
+    if !folder.is_dir() {
+        issues.push(ValidationIssue::MissingFolder(folder.to_path_buf()));
+        return Vec::new();
+    }
+    match fs::read_dir(folder) {
+        Ok(read_dir) => io::frame_ids_with_extension(read_dir, extension),
+        Err(error) => {
+            issues.push(ValidationIssue::ReadFolder(
+                folder.to_path_buf(),
+                error.to_string(),
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// Warns about gaps in frame counter names like `000041`, `000043`.
+/// Timestamps are not checked, consecutive counter frames differ by exactly 1.
+/// Other names are left out, they are reported on their own.
+fn report_missing_frames(folder: &Path, frame_ids: &[String], issues: &mut Vec<ValidationIssue>) {
+    let numbers: Vec<u64> = frame_ids
+        .iter()
+        .filter_map(|frame_id| frame_id.parse().ok())
+        .collect();
+    let is_counter = numbers
+        .windows(2)
+        .any(|pair| pair[1].checked_sub(pair[0]) == Some(1));
+    if !is_counter {
+        return;
+    }
+    for pair in numbers.windows(2) {
+        if pair[1] > pair[0] + 1 {
+            issues.push(ValidationIssue::MissingFrames {
+                folder: folder.to_path_buf(),
+                first: pair[0] + 1,
+                last: pair[1] - 1,
+            });
+        }
+    }
+}
+
+/// Number of records in the file, if its size fits the record size.
+fn record_count(path: &Path, record_size: u64, issues: &mut Vec<ValidationIssue>) -> Option<u64> {
+    let size = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(error) => {
+            issues.push(ValidationIssue::ReadFile(
+                path.to_path_buf(),
+                error.to_string(),
+            ));
+            return None;
+        }
+    };
+    if size % record_size != 0 {
+        issues.push(ValidationIssue::InvalidFileSize {
+            path: path.to_path_buf(),
+            size,
+            record_size,
+        });
+        return None;
+    }
+    Some(size / record_size)
+}
+
+fn check_point_file(frame: &str, path: &Path, issues: &mut Vec<ValidationIssue>) -> Option<u64> {
+    let point_count = record_count(path, POINT_SIZE, issues)?;
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            issues.push(ValidationIssue::ReadFile(
+                path.to_path_buf(),
+                error.to_string(),
+            ));
+            return None;
+        }
+    };
+    let non_finite = bytes
+        .chunks_exact(POINT_SIZE as usize)
+        .filter(|point| {
+            point[..12]
+                .chunks_exact(4)
+                .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+                .any(|coordinate| !coordinate.is_finite())
+        })
+        .count();
+    if non_finite > 0 {
+        issues.push(ValidationIssue::NonFiniteCoordinates {
+            frame: frame.to_string(),
+            count: non_finite,
+        });
+    }
+    Some(point_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sequence folder in the temp directory, removed again when dropped.
+    struct TestSequence(PathBuf);
+
+    impl TestSequence {
+        fn new(name: &str) -> Self {
+            let folder = std::env::temp_dir().join(format!(
+                "lidar_sequence_viewer_{name}_{}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&folder);
+            fs::create_dir_all(folder.join("velodyne")).unwrap();
+            fs::create_dir_all(folder.join("labels")).unwrap();
+            Self(folder)
+        }
+        fn points(self, frame_id: &str, points: &[[f32; 4]]) -> Self {
+            let bytes: Vec<u8> = points
+                .iter()
+                .flatten()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            self.bytes(&format!("velodyne/{frame_id}.bin"), &bytes)
+        }
+        fn labels(self, frame_id: &str, count: usize) -> Self {
+            self.bytes(
+                &format!("labels/{frame_id}.label"),
+                &vec![0; count * LABEL_SIZE as usize],
+            )
+        }
+        fn bytes(self, file: &str, bytes: &[u8]) -> Self {
+            fs::write(self.0.join(file), bytes).unwrap();
+            self
+        }
+        fn issues(&self) -> Vec<String> {
+            validate_sequence(&self.0)
+                .issues
+                .iter()
+                .map(|issue| issue.to_string())
+                .collect()
+        }
+    }
+
+    impl Drop for TestSequence {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const POINT: [f32; 4] = [1.0, 2.0, 3.0, 0.5];
+
+    fn missing_frames(frame_ids: &[&str]) -> Vec<(u64, u64)> {
+        let frame_ids: Vec<String> = frame_ids.iter().map(|id| id.to_string()).collect();
+        let mut issues = Vec::new();
+        report_missing_frames(Path::new("velodyne"), &frame_ids, &mut issues);
+        issues
+            .into_iter()
+            .map(|issue| match issue {
+                ValidationIssue::MissingFrames { first, last, .. } => (first, last),
+                issue => panic!("unexpected issue {issue}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn report_missing_frames_finds_gaps_of_counters() {
+        assert_eq!(missing_frames(&["000000", "000001", "000002"]), vec![]);
+        assert_eq!(
+            missing_frames(&["000100", "000101", "000103", "000107"]),
+            vec![(102, 102), (104, 106)]
+        );
+    }
+
+    #[test]
+    fn report_missing_frames_skips_timestamps_and_names() {
+        assert_eq!(
+            missing_frames(&["1618303231", "1618303233", "1618303236"]),
+            vec![]
+        );
+        assert_eq!(missing_frames(&["1618303231.52", "1618303231.62"]), vec![]);
+    }
+
+    #[test]
+    fn report_missing_frames_checks_counters_between_names() {
+        assert_eq!(missing_frames(&["000000", "000001", "frame_a"]), vec![]);
+        assert_eq!(
+            missing_frames(&["000000", "000001", "000003", "frame_a"]),
+            vec![(2, 2)]
+        );
+    }
+
+    #[test]
+    fn gaps_are_warnings() {
+        let report = ValidationReport {
+            folder: PathBuf::from("sequence"),
+            frame_count: 2,
+            issues: vec![ValidationIssue::MissingFrames {
+                folder: PathBuf::from("velodyne"),
+                first: 1,
+                last: 1,
+            }],
+        };
+        assert!(report.is_valid());
+        assert_eq!(report.warning_count(), 1);
+        assert_eq!(report.error_count(), 0);
+    }
+
+    #[test]
+    fn valid_sequence_has_no_issues() {
+        let sequence = TestSequence::new("valid")
+            .points("000000", &[POINT, POINT])
+            .labels("000000", 2)
+            .points("000001", &[POINT])
+            .labels("000001", 1);
+        assert!(sequence.issues().is_empty());
+    }
+
+    #[test]
+    fn point_file_size_must_fit_whole_points() {
+        let sequence = TestSequence::new("size")
+            .bytes("velodyne/000000.bin", &[0; 20])
+            .labels("000000", 1);
+        let issues = sequence.issues();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].contains("is not a multiple of 16 bytes"));
+    }
+
+    #[test]
+    fn point_and_label_counts_must_match() {
+        let sequence = TestSequence::new("count")
+            .points("000000", &[POINT, POINT, POINT])
+            .labels("000000", 2);
+        assert_eq!(
+            sequence.issues(),
+            vec!["Frame 000000 has 3 points but 2 labels"]
+        );
+    }
+
+    #[test]
+    fn coordinates_must_be_finite() {
+        let sequence = TestSequence::new("finite")
+            .points(
+                "000000",
+                &[
+                    POINT,
+                    [f32::NAN, 0.0, 0.0, 0.0],
+                    [0.0, f32::INFINITY, 0.0, 0.0],
+                ],
+            )
+            .labels("000000", 3);
+        assert_eq!(
+            sequence.issues(),
+            vec!["Frame 000000 has 2 points with NaN or infinite coordinates"]
+        );
+    }
+
+    #[test]
+    fn label_file_needs_a_point_file() {
+        let sequence = TestSequence::new("orphan")
+            .points("000000", &[POINT])
+            .labels("000000", 1)
+            .labels("000001", 1);
+        assert_eq!(
+            sequence.issues(),
+            vec!["Frame 000001 has labels but no points"]
+        );
+    }
+
+    #[test]
+    fn names_without_number_are_warnings() {
+        let sequence = TestSequence::new("names")
+            .points("000000", &[POINT])
+            .labels("000000", 1)
+            .points("frame_a", &[POINT])
+            .labels("frame_a", 1);
+        let report = validate_sequence(&sequence.0);
+        assert!(matches!(
+            report.issues.as_slice(),
+            [ValidationIssue::NonNumericName(path)] if path.ends_with("frame_a.bin")
+        ));
+        assert!(report.is_valid());
+    }
+}